@@ -7,7 +7,7 @@ use bevy::{
 use bevy_ggrs::RollbackApp;
 use std::time::Duration;
 
-use crate::{RollbackPostUpdate, RollbackPreUpdate};
+use crate::{RollCommandsSystems, RollbackPostUpdate, RollbackPreUpdate, RollbackSessionReset};
 
 /// Plugin for managing rollback audio effects in a Bevy application.
 ///
@@ -40,15 +40,25 @@ pub struct RollbackAudioPlugin;
 impl Plugin for RollbackAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Update, sync_rollback_sounds);
-        app.add_systems(RollbackPreUpdate, remove_finished_sounds);
-        app.add_systems(RollbackPostUpdate, start_rollback_sounds);
+        app.add_systems(
+            RollbackPreUpdate,
+            remove_finished_sounds.in_set(RollCommandsSystems),
+        );
+        app.add_systems(
+            RollbackPostUpdate,
+            start_rollback_sounds.in_set(RollCommandsSystems),
+        );
+        app.add_observer(stop_rollback_sounds);
 
         #[cfg(feature = "bevy_ggrs")]
         {
             app.rollback_component_with_clone::<RollbackAudioPlayer>();
             app.rollback_component_with_clone::<RollbackAudioPlayerStartTime>();
             app.rollback_component_with_clone::<PlaybackSettings>();
-            app.add_systems(RollbackPostUpdate, add_rollback_to_rollback_sounds);
+            app.add_systems(
+                RollbackPostUpdate,
+                add_rollback_to_rollback_sounds.in_set(RollCommandsSystems),
+            );
         }
     }
 }
@@ -177,6 +187,30 @@ pub fn start_rollback_sounds(
     }
 }
 
+/// Stops all rollback sounds when a new session starts.
+///
+/// Only the audio components are removed, the entities they were on may
+/// still be in use by the game.
+fn stop_rollback_sounds(
+    _reset: On<RollbackSessionReset>,
+    mut commands: Commands,
+    players: Query<Entity, With<RollbackAudioPlayer>>,
+    sinks: Query<&AudioSink, With<RollbackAudioPlayerInstance>>,
+) {
+    for entity in &players {
+        commands.entity(entity).remove::<(
+            RollbackAudioPlayer,
+            RollbackAudioPlayerStartTime,
+            PlaybackSettings,
+        )>();
+    }
+    // the instances are despawned by `sync_rollback_sounds` once their
+    // players are gone
+    for sink in &sinks {
+        sink.stop();
+    }
+}
+
 /// Automatically adds [`bevy_ggrs::Rollback`] to [`RollbackAudioPlayer`]s that are missing it.
 #[cfg(feature = "bevy_ggrs")]
 fn add_rollback_to_rollback_sounds(
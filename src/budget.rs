@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeSystems};
+
+/// What to do when the rollback schedules take longer than the
+/// [`RollbackBudget`] in a render frame.
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RollbackBudgetPolicy {
+    /// Log a warning when the budget starts being exceeded, and again only
+    /// once it has been within budget for a render frame.
+    #[default]
+    Warn,
+    /// Pause [`Time<Virtual>`] for the next render frame.
+    ///
+    /// `bevy_ggrs` advances frames based on [`Time`], so this gives the
+    /// session a render frame without new frames to catch up.
+    ///
+    /// Virtual time is global, so this also freezes every other system that
+    /// reads it, e.g. timers and animations, not just the rollback schedules.
+    Stall,
+    /// Skip presentation systems that run if
+    /// [`rollback_budget_allows_presentation`].
+    SkipPresentation,
+}
+
+/// Time budget for the rollback schedules in each render frame.
+///
+/// Added by [`RollbackSchedulePlugin::with_budget`](crate::RollbackSchedulePlugin::with_budget).
+/// The time spent is accumulated over all rollback frames run in a render
+/// frame, and reset in [`First`].
+#[derive(Resource, Reflect, Clone, Debug)]
+#[reflect(Resource)]
+pub struct RollbackBudget {
+    /// Time the rollback schedules may take per render frame.
+    pub budget: Duration,
+    /// What to do when the budget is exceeded.
+    pub policy: RollbackBudgetPolicy,
+    spent: Duration,
+    stalling: bool,
+    /// Whether the current overrun has already been warned about
+    warned: bool,
+}
+
+impl RollbackBudget {
+    pub fn new(budget: Duration, policy: RollbackBudgetPolicy) -> Self {
+        Self {
+            budget,
+            policy,
+            spent: Duration::ZERO,
+            stalling: false,
+            warned: false,
+        }
+    }
+
+    /// Time spent in the rollback schedules during this render frame.
+    pub fn spent(&self) -> Duration {
+        self.spent
+    }
+
+    /// Time left of the budget in this render frame.
+    pub fn remaining(&self) -> Duration {
+        self.budget.saturating_sub(self.spent)
+    }
+
+    /// Whether the rollback schedules took longer than the budget in this
+    /// render frame.
+    pub fn is_exceeded(&self) -> bool {
+        self.spent > self.budget
+    }
+
+    /// Whether virtual time is paused by [`RollbackBudgetPolicy::Stall`].
+    pub fn is_stalling(&self) -> bool {
+        self.stalling
+    }
+
+    pub(crate) fn record(&mut self, duration: Duration) {
+        self.spent += duration;
+    }
+}
+
+/// Run condition for presentation systems, e.g. visual effects, that may be
+/// skipped when the [`RollbackBudget`] is exceeded.
+///
+/// Only returns `false` if the policy is
+/// [`RollbackBudgetPolicy::SkipPresentation`] and the budget is exceeded.
+pub fn rollback_budget_allows_presentation(budget: Option<Res<RollbackBudget>>) -> bool {
+    budget.is_none_or(|budget| {
+        budget.policy != RollbackBudgetPolicy::SkipPresentation || !budget.is_exceeded()
+    })
+}
+
+pub(crate) fn add_budget(app: &mut App, budget: RollbackBudget) {
+    app.register_type::<RollbackBudget>()
+        .insert_resource(budget)
+        .add_systems(First, reset_rollback_budget.after(TimeSystems))
+        .add_systems(Last, enforce_rollback_budget);
+}
+
+fn reset_rollback_budget(
+    mut budget: ResMut<RollbackBudget>,
+    virtual_time: Option<ResMut<Time<Virtual>>>,
+) {
+    if budget.stalling {
+        budget.stalling = false;
+        if let Some(mut virtual_time) = virtual_time {
+            virtual_time.unpause();
+        }
+    }
+
+    budget.spent = Duration::ZERO;
+}
+
+fn enforce_rollback_budget(
+    mut budget: ResMut<RollbackBudget>,
+    virtual_time: Option<ResMut<Time<Virtual>>>,
+) {
+    if !budget.is_exceeded() {
+        budget.warned = false;
+        return;
+    }
+
+    match budget.policy {
+        RollbackBudgetPolicy::Warn => {
+            // don't flood the log during a sustained overrun
+            if !budget.warned {
+                budget.warned = true;
+                warn!(
+                    "Rollback schedules took {:?}, exceeding the budget of {:?}",
+                    budget.spent, budget.budget
+                );
+            }
+        }
+        RollbackBudgetPolicy::Stall => {
+            // don't unpause time that was paused by someone else
+            if let Some(mut virtual_time) = virtual_time.filter(|time| !time.is_paused()) {
+                virtual_time.pause();
+                budget.stalling = true;
+            }
+        }
+        RollbackBudgetPolicy::SkipPresentation => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::TestRollbackSchedule, RollbackSchedulePlugin, RollbackUpdate};
+    use bevy::time::TimePlugin;
+
+    fn slow_system() {
+        std::thread::sleep(Duration::from_millis(2));
+    }
+
+    #[test]
+    fn stalls_when_exceeded() {
+        let mut app = App::new();
+        app.add_plugins((
+            TimePlugin,
+            RollbackSchedulePlugin::new(TestRollbackSchedule)
+                .with_budget(Duration::from_millis(1), RollbackBudgetPolicy::Stall),
+        ))
+        .add_systems(RollbackUpdate, slow_system);
+
+        app.world_mut().run_schedule(TestRollbackSchedule);
+        let budget = app.world().resource::<RollbackBudget>();
+        assert!(budget.is_exceeded());
+        assert_eq!(budget.remaining(), Duration::ZERO);
+
+        app.world_mut().run_schedule(Last);
+        assert!(app.world().resource::<RollbackBudget>().is_stalling());
+        assert!(app.world().resource::<Time<Virtual>>().is_paused());
+
+        app.world_mut().run_schedule(First);
+        let budget = app.world().resource::<RollbackBudget>();
+        assert!(!budget.is_stalling());
+        assert_eq!(budget.spent(), Duration::ZERO);
+        assert!(!app.world().resource::<Time<Virtual>>().is_paused());
+    }
+
+    #[test]
+    fn warns_once_per_overrun() {
+        let mut app = App::new();
+        app.add_plugins(
+            RollbackSchedulePlugin::new(TestRollbackSchedule)
+                .with_budget(Duration::from_millis(1), RollbackBudgetPolicy::Warn),
+        )
+        .add_systems(RollbackUpdate, slow_system);
+
+        app.world_mut().run_schedule(TestRollbackSchedule);
+        app.world_mut().run_schedule(Last);
+        assert!(app.world().resource::<RollbackBudget>().warned);
+
+        app.world_mut().run_schedule(First);
+        app.world_mut().run_schedule(TestRollbackSchedule);
+        app.world_mut().run_schedule(Last);
+        assert!(app.world().resource::<RollbackBudget>().warned);
+
+        // within budget again
+        app.world_mut().run_schedule(First);
+        app.world_mut().run_schedule(Last);
+        assert!(!app.world().resource::<RollbackBudget>().warned);
+    }
+}
@@ -0,0 +1,382 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    ecs::{component::Tick, system::SystemChangeTick},
+    prelude::*,
+};
+
+use crate::{RollCommandsSystems, RollbackPostUpdate};
+
+/// Rollback-safe alternative to Bevy's [`Changed<T>`] filter.
+///
+/// Matches entities whose `T` was changed during the previous rollback frame.
+///
+/// Requires [`RollChangeDetectionPlugin<T>`] to be added.
+pub type RollChanged<T> = With<RollChangedFlag<T>>;
+
+/// Rollback-safe alternative to Bevy's [`Added<T>`] filter.
+///
+/// Matches entities whose `T` was added during the previous rollback frame.
+///
+/// Requires [`RollChangeDetectionPlugin<T>`] to be added.
+pub type RollAdded<T> = With<RollAddedFlag<T>>;
+
+/// Marks an entity whose `T` was changed during the previous rollback frame.
+///
+/// Usually used through the [`RollChanged<T>`] filter.
+#[derive(Component)]
+pub struct RollChangedFlag<T: Component>(PhantomData<T>);
+
+/// Marks an entity whose `T` was added during the previous rollback frame.
+///
+/// Usually used through the [`RollAdded<T>`] filter.
+#[derive(Component)]
+pub struct RollAddedFlag<T: Component>(PhantomData<T>);
+
+// manual impls, since derives would require `T: Clone`/`T: Copy`
+impl<T: Component> Default for RollChangedFlag<T> {
+    fn default() -> Self {
+        Self(default())
+    }
+}
+
+impl<T: Component> Clone for RollChangedFlag<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Component> Copy for RollChangedFlag<T> {}
+
+impl<T: Component> Default for RollAddedFlag<T> {
+    fn default() -> Self {
+        Self(default())
+    }
+}
+
+impl<T: Component> Clone for RollAddedFlag<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Component> Copy for RollAddedFlag<T> {}
+
+/// System set containing the systems that update [`RollChangedFlag`]s and
+/// [`RollAddedFlag`]s at the end of [`RollbackPostUpdate`].
+///
+/// Systems in [`RollbackPostUpdate`] that modify tracked components should be
+/// ordered before this set, or their changes will be reported a frame late.
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RollChangeDetectionSystems;
+
+/// The change tick at which the current rollback frame started.
+///
+/// Bevy's change ticks are not restored on rollback, so instead of comparing
+/// against the last run of a system, we compare against the tick recorded by
+/// the [`RollbackSchedulePlugin`](crate::RollbackSchedulePlugin) right before
+/// running the rollback schedules. Writes made between rollback frames, e.g.
+/// by loading a snapshot or by render interpolation, are therefore never
+/// reported, which keeps the flags the same when a frame is resimulated.
+#[derive(Resource, Default, Debug)]
+pub(crate) struct RollChangeBaseline(Tick);
+
+/// Records the start of a rollback frame, if change detection is enabled.
+pub(crate) fn begin_roll_change_frame(world: &mut World) {
+    if !world.contains_resource::<RollChangeBaseline>() {
+        return;
+    }
+
+    // systems in this frame run at later ticks, while anything written so far
+    // is at most as new as the returned one
+    let tick = world.increment_change_tick();
+    world.resource_mut::<RollChangeBaseline>().0 = tick;
+}
+
+/// Tracks additions and changes to `T` in a rollback-safe way, enabling the
+/// [`RollChanged<T>`] and [`RollAdded<T>`] query filters.
+///
+/// Depends on the [`RollbackSchedulePlugin`](crate::RollbackSchedulePlugin).
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_roll_safe::prelude::*;
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// fn log_damage(players: Query<&Health, RollChanged<Health>>) {
+///     for health in &players {
+///         info!("health changed to {}", health.0);
+///     }
+/// }
+///
+/// # fn start() {
+/// # let mut app = App::new();
+/// app.add_plugins((
+///     RollbackSchedulePlugin::new(FixedUpdate),
+///     RollChangeDetectionPlugin::<Health>::default(),
+/// ))
+/// .add_systems(RollbackUpdate, log_damage);
+/// # }
+/// ```
+pub struct RollChangeDetectionPlugin<T: Component>(PhantomData<T>);
+
+impl<T: Component> Default for RollChangeDetectionPlugin<T> {
+    fn default() -> Self {
+        Self(default())
+    }
+}
+
+impl<T: Component> Plugin for RollChangeDetectionPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            RollbackPostUpdate,
+            track_roll_changes::<T>
+                .in_set(RollChangeDetectionSystems)
+                .in_set(RollCommandsSystems),
+        );
+
+        // shared between all tracked component types
+        app.init_resource::<RollChangeBaseline>();
+
+        #[cfg(feature = "bevy_ggrs")]
+        {
+            use bevy_ggrs::RollbackApp;
+            app.rollback_component_with_copy::<RollChangedFlag<T>>()
+                .rollback_component_with_copy::<RollAddedFlag<T>>();
+        }
+    }
+}
+
+/// Updates [`RollChangedFlag<T>`] and [`RollAddedFlag<T>`] to reflect the
+/// changes to `T` made since the start of the current rollback frame.
+#[allow(clippy::type_complexity)]
+fn track_roll_changes<T: Component>(
+    mut commands: Commands,
+    baseline: Res<RollChangeBaseline>,
+    system_ticks: SystemChangeTick,
+    components: Query<(
+        Entity,
+        Ref<T>,
+        Has<RollChangedFlag<T>>,
+        Has<RollAddedFlag<T>>,
+    )>,
+    removed: Query<
+        Entity,
+        (
+            Without<T>,
+            Or<(With<RollChangedFlag<T>>, With<RollAddedFlag<T>>)>,
+        ),
+    >,
+) {
+    let this_run = system_ticks.this_run();
+
+    for (entity, component, flagged_changed, flagged_added) in &components {
+        let changed = component.last_changed().is_newer_than(baseline.0, this_run);
+        let added = component.added().is_newer_than(baseline.0, this_run);
+
+        match (changed, flagged_changed) {
+            (true, false) => {
+                commands
+                    .entity(entity)
+                    .insert(RollChangedFlag::<T>::default());
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<RollChangedFlag<T>>();
+            }
+            _ => {}
+        }
+
+        match (added, flagged_added) {
+            (true, false) => {
+                commands
+                    .entity(entity)
+                    .insert(RollAddedFlag::<T>::default());
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<RollAddedFlag<T>>();
+            }
+            _ => {}
+        }
+    }
+
+    for entity in &removed {
+        commands
+            .entity(entity)
+            .remove::<(RollChangedFlag<T>, RollAddedFlag<T>)>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RollbackSchedulePlugin, RollbackUpdate};
+
+    #[derive(Component, Default)]
+    struct Health(u32);
+
+    #[derive(Resource, Default)]
+    struct Damage(u32);
+
+    #[derive(Resource, Default)]
+    struct ChangedCount(usize);
+
+    fn apply_damage(damage: Res<Damage>, mut players: Query<&mut Health>) {
+        if damage.0 == 0 {
+            return;
+        }
+        for mut health in &mut players {
+            health.0 = health.0.saturating_sub(damage.0);
+        }
+    }
+
+    fn count_changed(mut count: ResMut<ChangedCount>, players: Query<(), RollChanged<Health>>) {
+        count.0 = players.iter().count();
+    }
+
+    fn spawn_player(mut commands: Commands) {
+        commands.spawn(Health(10));
+    }
+
+    #[test]
+    fn changes_are_reported_the_following_frame() {
+        let mut app = App::new();
+        app.add_plugins((
+            RollbackSchedulePlugin::new(Update),
+            RollChangeDetectionPlugin::<Health>::default(),
+        ))
+        .init_resource::<Damage>()
+        .init_resource::<ChangedCount>()
+        .add_systems(
+            RollbackUpdate,
+            (count_changed, apply_damage, spawn_player.run_if(run_once)).chain(),
+        );
+
+        // spawned during the first frame, so it counts as added
+        app.update();
+        let player = app
+            .world_mut()
+            .query_filtered::<Entity, With<Health>>()
+            .single(app.world())
+            .unwrap();
+        assert!(app.world().get::<RollAddedFlag<Health>>(player).is_some());
+        assert!(app.world().get::<RollChangedFlag<Health>>(player).is_some());
+
+        app.update();
+        assert_eq!(app.world().resource::<ChangedCount>().0, 1);
+        assert!(app.world().get::<RollAddedFlag<Health>>(player).is_none());
+        assert!(app.world().get::<RollChangedFlag<Health>>(player).is_none());
+
+        app.world_mut().resource_mut::<Damage>().0 = 1;
+        app.update();
+        assert_eq!(app.world().resource::<ChangedCount>().0, 0);
+        assert!(app.world().get::<RollChangedFlag<Health>>(player).is_some());
+
+        app.world_mut().resource_mut::<Damage>().0 = 0;
+        app.update();
+        assert_eq!(app.world().resource::<ChangedCount>().0, 1);
+        assert!(app.world().get::<RollChangedFlag<Health>>(player).is_none());
+    }
+
+    #[test]
+    fn changes_between_frames_are_ignored() {
+        let mut app = App::new();
+        app.add_plugins((
+            RollbackSchedulePlugin::new(FixedUpdate),
+            RollChangeDetectionPlugin::<Health>::default(),
+        ));
+
+        // neither spawning nor changing outside rollback frames counts
+        let player = app.world_mut().spawn(Health(10)).id();
+        app.world_mut().run_schedule(FixedUpdate);
+        assert!(app.world().get::<RollAddedFlag<Health>>(player).is_none());
+
+        for _ in 0..3 {
+            app.world_mut().get_mut::<Health>(player).unwrap().0 += 1;
+            app.world_mut().run_schedule(FixedUpdate);
+            assert!(app.world().get::<RollChangedFlag<Health>>(player).is_none());
+        }
+    }
+
+    #[cfg(feature = "bevy_ggrs")]
+    #[test]
+    fn resimulation_reports_the_same_changes() {
+        use crate::test_utils::ggrs_test_app;
+        use bevy_ggrs::{
+            AddRollbackCommandExtension, AdvanceWorld, LoadWorld, RollbackApp, RollbackFrameCount,
+            SaveWorld,
+        };
+
+        #[derive(Component, Clone, Copy)]
+        struct Armor(u32);
+
+        /// `(frame, changed, added)` as seen by each frame, not rolled back
+        #[derive(Resource, Default)]
+        struct Seen(Vec<(i32, bool, bool)>);
+
+        fn damage_on_frame_2(frame: Res<RollbackFrameCount>, mut armor: Query<&mut Armor>) {
+            if frame.0 == 2 {
+                for mut armor in &mut armor {
+                    armor.0 -= 1;
+                }
+            }
+        }
+
+        #[allow(clippy::type_complexity)]
+        fn record(
+            frame: Res<RollbackFrameCount>,
+            mut seen: ResMut<Seen>,
+            armor: Query<(Has<RollChangedFlag<Armor>>, Has<RollAddedFlag<Armor>>)>,
+        ) {
+            for (changed, added) in &armor {
+                seen.0.push((frame.0, changed, added));
+            }
+        }
+
+        fn advance(world: &mut World) {
+            world.resource_mut::<RollbackFrameCount>().0 += 1;
+            world.run_schedule(AdvanceWorld);
+            // e.g. render interpolation writing between rollback frames
+            for mut armor in world.query::<&mut Armor>().iter_mut(world) {
+                armor.set_changed();
+            }
+        }
+
+        let mut app = ggrs_test_app();
+        app.add_plugins(RollChangeDetectionPlugin::<Armor>::default())
+            .rollback_component_with_copy::<Armor>()
+            .init_resource::<Seen>()
+            .add_systems(
+                RollbackUpdate,
+                (
+                    record,
+                    damage_on_frame_2,
+                    (|mut commands: Commands| {
+                        commands.spawn(Armor(5)).add_rollback();
+                    })
+                    .run_if(|frame: Res<RollbackFrameCount>| frame.0 == 1),
+                )
+                    .chain(),
+            );
+
+        for _ in 0..4 {
+            app.world_mut().run_schedule(SaveWorld);
+            advance(app.world_mut());
+        }
+
+        let simulated = std::mem::take(&mut app.world_mut().resource_mut::<Seen>().0);
+        assert_eq!(
+            simulated,
+            vec![(2, true, true), (3, true, false), (4, false, false)]
+        );
+
+        app.world_mut().resource_mut::<RollbackFrameCount>().0 = 1;
+        app.world_mut().run_schedule(LoadWorld);
+        for _ in 0..3 {
+            advance(app.world_mut());
+            app.world_mut().run_schedule(SaveWorld);
+        }
+
+        assert_eq!(app.world().resource::<Seen>().0, simulated);
+    }
+}
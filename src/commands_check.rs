@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy::{
+    ecs::{
+        component::ComponentId,
+        query::ComponentAccessKind,
+        schedule::{
+            graph::{DiGraph, Direction},
+            InternedSystemSet, NodeId, ScheduleBuildError, ScheduleBuildPass, ScheduleBuildWarning,
+            ScheduleGraph, SystemKey, SystemSetKey,
+        },
+    },
+    prelude::*,
+};
+
+/// System set containing the systems added by this crate's plugins that issue
+/// commands in the rollback schedules.
+///
+/// None of them spawn entities, and they each touch different components, so
+/// their commands don't depend on each other's order, and
+/// [`RollbackSchedulePlugin::with_commands_conflict_check`](crate::RollbackSchedulePlugin::with_commands_conflict_check)
+/// doesn't report them against each other. Pass it to
+/// [`RollbackSchedulePlugin::allow_commands_conflict`](crate::RollbackSchedulePlugin::allow_commands_conflict)
+/// to also silence conflicts with your own systems.
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RollCommandsSystems;
+
+/// Components accessed by a system, `None` if it may access any component.
+type ComponentAccess = Option<BTreeSet<ComponentId>>;
+
+/// Schedule build pass that fails the build if two systems that issue
+/// deferred commands are not ordered relative to each other.
+///
+/// The order commands are applied in is itself the hazard, e.g. two systems
+/// that only spawn entities still get their `Rollback` ids in a different
+/// order, so any such pair conflicts, whatever components they access. The
+/// components accessed by both systems' queries are only used to make the
+/// error more helpful. Systems that are `ambiguous_with_all` are ignored, as
+/// are pairs of systems in the `allowed` sets.
+///
+/// Added by [`RollbackSchedulePlugin::with_commands_conflict_check`](crate::RollbackSchedulePlugin::with_commands_conflict_check).
+#[derive(Debug, Default)]
+pub(crate) struct CommandsConflictPass {
+    /// See [`RollbackSchedulePlugin::allow_commands_conflict`](crate::RollbackSchedulePlugin::allow_commands_conflict).
+    pub(crate) allowed: Vec<(InternedSystemSet, InternedSystemSet)>,
+}
+
+impl ScheduleBuildPass for CommandsConflictPass {
+    type EdgeOptions = ();
+
+    fn add_dependency(&mut self, _from: NodeId, _to: NodeId, _options: Option<&Self::EdgeOptions>) {
+    }
+
+    fn collapse_set(
+        &mut self,
+        _set: SystemSetKey,
+        _systems: &[SystemKey],
+        _dependency_flattening: &DiGraph<NodeId>,
+    ) -> impl Iterator<Item = (NodeId, NodeId)> {
+        std::iter::empty()
+    }
+
+    fn build(
+        &mut self,
+        world: &mut World,
+        graph: &mut ScheduleGraph,
+        dependency_flattened: &mut DiGraph<SystemKey>,
+    ) -> Result<(), ScheduleBuildError> {
+        let deferred: Vec<(SystemKey, ComponentAccess)> = graph
+            .systems
+            .iter()
+            .filter(|(key, system, _)| {
+                system.has_deferred()
+                    && !system.is_exclusive()
+                    && !graph.ambiguous_with_all.contains(&NodeId::System(*key))
+            })
+            .map(|(key, _, _)| (key, component_access(graph, key)))
+            .collect();
+
+        let after: BTreeMap<SystemKey, BTreeSet<SystemKey>> = deferred
+            .iter()
+            .map(|(key, _)| (*key, reachable(dependency_flattened, *key)))
+            .collect();
+
+        let mut allowed = BTreeSet::new();
+        for (a, b) in &self.allowed {
+            let a_systems = systems_in_set(graph, *a);
+            let b_systems = systems_in_set(graph, *b);
+            for &a in &a_systems {
+                for &b in &b_systems {
+                    allowed.insert((a, b));
+                    allowed.insert((b, a));
+                }
+            }
+        }
+
+        let mut conflicts = Vec::new();
+
+        for (i, (a, a_access)) in deferred.iter().enumerate() {
+            for (b, b_access) in &deferred[i + 1..] {
+                if after[a].contains(b) || after[b].contains(a) || allowed.contains(&(*a, *b)) {
+                    continue;
+                }
+
+                let shared: Vec<ComponentId> = match (a_access, b_access) {
+                    (Some(a_access), Some(b_access)) => {
+                        a_access.intersection(b_access).copied().collect()
+                    }
+                    // at least one of them may access anything
+                    (Some(access), None) | (None, Some(access)) => access.iter().copied().collect(),
+                    (None, None) => Vec::new(),
+                };
+
+                conflicts.push((*a, *b, shared));
+            }
+        }
+
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+
+        for (a, b, components) in graph.conflicts_to_string(&conflicts, world.components()) {
+            if components.is_empty() {
+                error!(
+                    "{a} and {b} both issue commands, \
+                     but are not ordered relative to each other"
+                );
+            } else {
+                error!(
+                    "{a} and {b} both issue commands and access {components:?}, \
+                     but are not ordered relative to each other"
+                );
+            }
+        }
+
+        Err(ScheduleBuildError::Elevated(
+            ScheduleBuildWarning::Ambiguity(conflicts),
+        ))
+    }
+}
+
+fn component_access(graph: &ScheduleGraph, key: SystemKey) -> ComponentAccess {
+    let access = graph.systems.get(key)?.access.combined_access();
+    let components = access.try_iter_component_access().ok()?;
+    Some(
+        components
+            .filter_map(|kind| match kind {
+                ComponentAccessKind::Shared(id) | ComponentAccessKind::Exclusive(id) => Some(id),
+                ComponentAccessKind::Archetypal(_) => None,
+            })
+            .collect(),
+    )
+}
+
+/// Systems in the set, directly or through nested sets.
+fn systems_in_set(graph: &ScheduleGraph, set: InternedSystemSet) -> BTreeSet<SystemKey> {
+    let mut systems = BTreeSet::new();
+    let Some((key, _, _)) = graph
+        .system_sets
+        .iter()
+        .find(|(_, other, _)| *other == &*set)
+    else {
+        return systems;
+    };
+
+    let hierarchy = graph.hierarchy().graph();
+    let mut stack = vec![NodeId::Set(key)];
+    while let Some(node) = stack.pop() {
+        for child in hierarchy.neighbors_directed(node, Direction::Outgoing) {
+            match child {
+                NodeId::System(system) => {
+                    systems.insert(system);
+                }
+                NodeId::Set(_) => stack.push(child),
+            }
+        }
+    }
+    systems
+}
+
+fn reachable(graph: &DiGraph<SystemKey>, from: SystemKey) -> BTreeSet<SystemKey> {
+    let mut visited = BTreeSet::new();
+    let mut stack = vec![from];
+    while let Some(node) = stack.pop() {
+        for next in graph.neighbors_directed(node, Direction::Outgoing) {
+            if visited.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    visited
+}
@@ -0,0 +1,533 @@
+use std::{any::TypeId, collections::BTreeMap, collections::VecDeque, fmt, marker::PhantomData};
+
+use crate::RollbackSessionReset;
+use bevy::{ecs::reflect::AppTypeRegistry, prelude::*};
+use bevy_ggrs::{
+    ggrs::Config, Rollback, RollbackFrameCount, RollbackOrdered, SaveWorld, SaveWorldSystems,
+    Session,
+};
+
+/// Debug plugin that reports *which* component or resource caused a desync.
+///
+/// Each time a frame is saved, the hashes of the tracked components on
+/// rollback entities are recorded into a ring buffer. When the same frame is saved
+/// again after being resimulated in a [`Session::SyncTest`], the hashes are
+/// compared and the first difference is logged and stored in
+/// [`DesyncDetector::last_desync`].
+///
+/// Other sessions resimulate frames with corrected inputs, which legitimately
+/// changes the result, so their frames are only recorded.
+///
+/// Components are tracked once added with
+/// [`DesyncDetectorPlugin::with_component`]. Only add types that are rolled
+/// back, since other components, e.g. [`Name`], are not restored when loading
+/// a snapshot and would be reported as desyncs. Tracked types must be
+/// registered for reflection with `#[reflect(Hash)]`, otherwise a warning is
+/// logged and they are skipped.
+///
+/// These are typically the same types that you register with
+/// `checksum_component_with_hash`, but `bevy_ggrs` doesn't expose which types
+/// those are, so they have to be listed again here:
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_ggrs::prelude::*;
+/// # use bevy_roll_safe::DesyncDetectorPlugin;
+/// # type MyConfig = GgrsConfig<u8>;
+/// #[derive(Component, Reflect, Hash, Clone, Copy)]
+/// #[reflect(Component, Hash)]
+/// struct Health(u32);
+///
+/// #[derive(Resource, Reflect, Hash, Clone, Copy)]
+/// #[reflect(Resource, Hash)]
+/// struct BossHealth(u32);
+///
+/// # fn start() {
+/// # let mut app = App::new();
+/// app.register_type::<Health>()
+///     .rollback_component_with_copy::<Health>()
+///     .checksum_component_with_hash::<Health>()
+///     .rollback_resource_with_copy::<BossHealth>()
+///     .add_plugins(
+///         DesyncDetectorPlugin::<MyConfig>::default()
+///             .with_component::<Health>()
+///             .with_resource::<BossHealth>(),
+///     );
+/// # }
+/// ```
+///
+/// Likewise, resources are only tracked once added with
+/// [`DesyncDetectorPlugin::with_resource`].
+pub struct DesyncDetectorPlugin<C: Config> {
+    max_frames: usize,
+    components: Vec<TypeId>,
+    resources: Vec<TypeId>,
+    _phantom: PhantomData<C>,
+}
+
+impl<C: Config> Default for DesyncDetectorPlugin<C> {
+    fn default() -> Self {
+        Self {
+            max_frames: 128,
+            components: Vec::new(),
+            resources: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<C: Config> DesyncDetectorPlugin<C> {
+    /// Sets the number of frames of hashes to keep. Must be at least the
+    /// `SyncTest` check distance, or the max prediction window.
+    pub fn with_max_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = max_frames;
+        self
+    }
+
+    /// Track the given (reflected and hashable) component on rollback
+    /// entities.
+    pub fn with_component<T: Component + Reflect>(mut self) -> Self {
+        self.components.push(TypeId::of::<T>());
+        self
+    }
+
+    /// Also track the given (reflected and hashable) resource.
+    pub fn with_resource<R: Resource + Reflect>(mut self) -> Self {
+        self.resources.push(TypeId::of::<R>());
+        self
+    }
+}
+
+impl<C: Config> Plugin for DesyncDetectorPlugin<C> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DesyncDetector {
+            max_frames: self.max_frames,
+            components: self.components.clone(),
+            resources: self.resources.clone(),
+            frames: VecDeque::with_capacity(self.max_frames),
+            last_desync: None,
+        })
+        .add_systems(
+            SaveWorld,
+            record_hashes::<C>.after(SaveWorldSystems::Checksum),
+        )
+        .add_observer(forget_frames);
+    }
+}
+
+/// Identifies a hashed value within a frame.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DesyncSource {
+    /// A resource, by type path
+    Resource(&'static str),
+    /// A component on a rollback entity
+    Component {
+        /// The order of the rollback entity, stable across rollbacks
+        rollback_order: u64,
+        /// The entity the component was on when the desync was detected
+        entity: Entity,
+        /// The type path of the component
+        component: &'static str,
+    },
+}
+
+impl fmt::Display for DesyncSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DesyncSource::Resource(resource) => write!(f, "resource {resource}"),
+            DesyncSource::Component {
+                rollback_order,
+                entity,
+                component,
+            } => write!(
+                f,
+                "component {component} on entity {entity} (rollback #{rollback_order})"
+            ),
+        }
+    }
+}
+
+/// The first difference found when a frame was resimulated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesyncReport {
+    pub frame: i32,
+    pub source: DesyncSource,
+    /// Hash when the frame was first simulated, `None` if it didn't exist.
+    pub expected: Option<u64>,
+    /// Hash after resimulating the frame, `None` if it doesn't exist.
+    pub actual: Option<u64>,
+}
+
+impl fmt::Display for DesyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "desync in frame {}: {} was {:?}, expected {:?}",
+            self.frame, self.source, self.actual, self.expected
+        )
+    }
+}
+
+/// Key that is stable across rollbacks (unlike [`DesyncSource`], which
+/// includes the current [`Entity`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum HashKey {
+    Resource(&'static str),
+    Component(u64, &'static str),
+}
+
+#[derive(Default)]
+struct FrameHashes {
+    frame: i32,
+    hashes: BTreeMap<HashKey, (u64, Option<Entity>)>,
+}
+
+/// Ring buffer of per-component hashes for recently saved frames.
+///
+/// Added by [`DesyncDetectorPlugin`].
+#[derive(Resource)]
+pub struct DesyncDetector {
+    max_frames: usize,
+    components: Vec<TypeId>,
+    resources: Vec<TypeId>,
+    /// Newest at the front.
+    frames: VecDeque<FrameHashes>,
+    last_desync: Option<DesyncReport>,
+}
+
+impl DesyncDetector {
+    /// The most recently detected desync, if any.
+    pub fn last_desync(&self) -> Option<&DesyncReport> {
+        self.last_desync.as_ref()
+    }
+
+    /// Records hashes for a frame, returning the first difference if the
+    /// frame had been recorded before and `compare` is set.
+    fn record(&mut self, new: FrameHashes, compare: bool) -> Option<DesyncReport> {
+        let Some(old) = self.frames.iter_mut().find(|old| old.frame == new.frame) else {
+            self.frames.push_front(new);
+            self.frames.truncate(self.max_frames);
+            return None;
+        };
+
+        let report = compare.then(|| first_difference(old, &new)).flatten();
+
+        // keep the latest hashes, so the same difference isn't reported again
+        *old = new;
+
+        if report.is_some() {
+            self.last_desync.clone_from(&report);
+        }
+
+        report
+    }
+}
+
+fn first_difference(old: &FrameHashes, new: &FrameHashes) -> Option<DesyncReport> {
+    let mut keys: Vec<_> = old.hashes.keys().chain(new.hashes.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter().find_map(|key| {
+        let expected = old.hashes.get(key);
+        let actual = new.hashes.get(key);
+        if expected.map(|(hash, _)| hash) == actual.map(|(hash, _)| hash) {
+            return None;
+        }
+
+        let source = match *key {
+            HashKey::Resource(resource) => DesyncSource::Resource(resource),
+            HashKey::Component(rollback_order, component) => DesyncSource::Component {
+                rollback_order,
+                entity: actual
+                    .or(expected)
+                    .and_then(|(_, entity)| *entity)
+                    .unwrap_or(Entity::PLACEHOLDER),
+                component,
+            },
+        };
+
+        Some(DesyncReport {
+            frame: new.frame,
+            source,
+            expected: expected.map(|(hash, _)| *hash),
+            actual: actual.map(|(hash, _)| *hash),
+        })
+    })
+}
+
+/// Hashes recorded in the previous session can't be compared with new ones.
+fn forget_frames(_reset: On<RollbackSessionReset>, mut detector: ResMut<DesyncDetector>) {
+    detector.frames.clear();
+}
+
+fn record_hashes<C: Config>(world: &mut World) {
+    let frame = world.resource::<RollbackFrameCount>().0;
+    let mut hashes = FrameHashes { frame, ..default() };
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+
+    let components: Vec<_> = world
+        .resource::<DesyncDetector>()
+        .components
+        .iter()
+        .filter_map(|type_id| {
+            let Some(registration) = registry.get(*type_id) else {
+                warn_once!(
+                    "Desync detector component {type_id:?} is not registered for reflection"
+                );
+                return None;
+            };
+            let Some(reflect_component) = registration.data::<ReflectComponent>() else {
+                warn_once!(
+                    "Desync detector component {} doesn't reflect Component",
+                    registration.type_info().type_path()
+                );
+                return None;
+            };
+            Some((registration, reflect_component))
+        })
+        .collect();
+
+    let mut rollback_entities = world.query::<(EntityRef, &Rollback)>();
+    let rollback_ordered = world.resource::<RollbackOrdered>();
+
+    for (entity_ref, rollback) in rollback_entities.iter(world) {
+        let rollback_order = rollback_ordered.order(*rollback);
+
+        for (registration, reflect_component) in &components {
+            let Some(component) = reflect_component.reflect(entity_ref) else {
+                continue;
+            };
+
+            let type_path = registration.type_info().type_path();
+            let Some(hash) = component.reflect_hash() else {
+                warn_once!("Desync detector component {type_path} doesn't reflect Hash");
+                continue;
+            };
+
+            hashes.hashes.insert(
+                HashKey::Component(rollback_order, type_path),
+                (hash, Some(entity_ref.id())),
+            );
+        }
+    }
+
+    let detector = world.resource::<DesyncDetector>();
+
+    for type_id in &detector.resources {
+        let Some(registration) = registry.get(*type_id) else {
+            warn_once!("Desync detector resource {type_id:?} is not registered for reflection");
+            continue;
+        };
+
+        let type_path = registration.type_info().type_path();
+        let Some(reflect_resource) = registration.data::<ReflectResource>() else {
+            warn_once!("Desync detector resource {type_path} doesn't reflect Resource");
+            continue;
+        };
+
+        let Ok(resource) = reflect_resource.reflect(&*world) else {
+            continue;
+        };
+
+        let Some(hash) = resource.reflect_hash() else {
+            warn_once!("Desync detector resource {type_path} doesn't reflect Hash");
+            continue;
+        };
+
+        hashes
+            .hashes
+            .insert(HashKey::Resource(type_path), (hash, None));
+    }
+
+    // only sync test sessions are expected to resimulate frames identically
+    let compare = matches!(
+        world.get_resource::<Session<C>>(),
+        Some(Session::SyncTest(_))
+    );
+
+    if let Some(report) = world
+        .resource_mut::<DesyncDetector>()
+        .record(hashes, compare)
+    {
+        error!("{report}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::advance_and_save;
+    use bevy_ggrs::{
+        ggrs::{PlayerType, SessionBuilder},
+        AddRollbackCommandExtension, AdvanceWorld, GgrsConfig, LoadWorld, RollbackApp,
+        SnapshotPlugin,
+    };
+
+    type TestConfig = GgrsConfig<u8>;
+
+    #[derive(Component, Reflect, Hash, Clone, Copy)]
+    #[reflect(Component, Hash)]
+    struct Health(u32);
+
+    /// Not rolled back, so it isn't restored when loading
+    #[derive(Component, Reflect, Hash, Clone, Copy)]
+    #[reflect(Component, Hash)]
+    struct Presentation(u32);
+
+    /// Non-deterministic on purpose
+    #[derive(Resource, Default)]
+    struct Calls(u32);
+
+    fn damage(mut calls: ResMut<Calls>, mut players: Query<&mut Health>) {
+        calls.0 += 1;
+        for mut health in &mut players {
+            health.0 = health.0.saturating_sub(calls.0);
+        }
+    }
+
+    fn sync_test_session() -> Session<TestConfig> {
+        let session = SessionBuilder::new()
+            .with_num_players(1)
+            .add_player(PlayerType::Local, 0)
+            .unwrap()
+            .start_synctest_session()
+            .unwrap();
+        Session::SyncTest(session)
+    }
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            SnapshotPlugin,
+            DesyncDetectorPlugin::<TestConfig>::default().with_component::<Health>(),
+        ))
+        .register_type::<Health>()
+        .rollback_component_with_copy::<Health>()
+        .init_resource::<Calls>()
+        .add_systems(AdvanceWorld, damage);
+
+        app.world_mut().commands().spawn(Health(100)).add_rollback();
+        app.world_mut().flush();
+        app
+    }
+
+    #[test]
+    fn reports_diverging_component() {
+        let mut app = app();
+        app.insert_resource(sync_test_session());
+
+        app.world_mut().run_schedule(SaveWorld);
+        advance_and_save(app.world_mut());
+        advance_and_save(app.world_mut());
+        assert!(app
+            .world()
+            .resource::<DesyncDetector>()
+            .last_desync()
+            .is_none());
+
+        // resimulate frame 1 and 2
+        app.world_mut().resource_mut::<RollbackFrameCount>().0 = 0;
+        app.world_mut().run_schedule(LoadWorld);
+        advance_and_save(app.world_mut());
+
+        let report = app
+            .world()
+            .resource::<DesyncDetector>()
+            .last_desync()
+            .expect("desync should be detected")
+            .clone();
+
+        assert_eq!(report.frame, 1);
+        assert!(matches!(
+            report.source,
+            DesyncSource::Component {
+                rollback_order: 0,
+                component,
+                ..
+            } if component.ends_with("Health")
+        ));
+    }
+
+    #[test]
+    fn ignores_untracked_components() {
+        let mut app = App::new();
+        app.add_plugins((
+            SnapshotPlugin,
+            DesyncDetectorPlugin::<TestConfig>::default().with_component::<Health>(),
+        ))
+        .register_type::<Health>()
+        .register_type::<Presentation>()
+        .rollback_component_with_copy::<Health>()
+        .insert_resource(sync_test_session())
+        .add_systems(AdvanceWorld, |mut query: Query<&mut Presentation>| {
+            for mut presentation in &mut query {
+                presentation.0 += 1;
+            }
+        });
+
+        app.world_mut()
+            .commands()
+            .spawn((Health(100), Presentation(0)))
+            .add_rollback();
+        app.world_mut().flush();
+
+        app.world_mut().run_schedule(SaveWorld);
+        advance_and_save(app.world_mut());
+        advance_and_save(app.world_mut());
+
+        // `Presentation` is not restored, so it differs when resimulating
+        app.world_mut().resource_mut::<RollbackFrameCount>().0 = 0;
+        app.world_mut().run_schedule(LoadWorld);
+        advance_and_save(app.world_mut());
+
+        assert!(app
+            .world()
+            .resource::<DesyncDetector>()
+            .last_desync()
+            .is_none());
+    }
+
+    #[test]
+    fn ignores_resimulation_outside_sync_test() {
+        // without a sync test session, resimulated frames may differ because
+        // of corrected inputs, as in p2p sessions
+        let mut app = app();
+
+        app.world_mut().run_schedule(SaveWorld);
+        advance_and_save(app.world_mut());
+        advance_and_save(app.world_mut());
+
+        app.world_mut().resource_mut::<RollbackFrameCount>().0 = 0;
+        app.world_mut().run_schedule(LoadWorld);
+        advance_and_save(app.world_mut());
+
+        assert!(app
+            .world()
+            .resource::<DesyncDetector>()
+            .last_desync()
+            .is_none());
+    }
+
+    #[test]
+    fn session_reset_forgets_frames() {
+        let mut app = app();
+        app.insert_resource(sync_test_session());
+
+        advance_and_save(app.world_mut());
+
+        // a new match starts from frame 0 again
+        app.world_mut().trigger(RollbackSessionReset);
+        app.world_mut().resource_mut::<RollbackFrameCount>().0 = 0;
+        app.world_mut().resource_mut::<Calls>().0 = 0;
+        advance_and_save(app.world_mut());
+
+        assert!(app
+            .world()
+            .resource::<DesyncDetector>()
+            .last_desync()
+            .is_none());
+    }
+}
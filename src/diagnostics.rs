@@ -0,0 +1,208 @@
+use std::time::Duration;
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    ecs::schedule::{InternedScheduleLabel, ScheduleLabel},
+    platform::collections::HashMap,
+    prelude::*,
+};
+
+use crate::{RollbackPostUpdate, RollbackPreUpdate, RollbackStateTransition, RollbackUpdate};
+
+/// Records how much time the rollback schedules take into Bevy's
+/// [`Diagnostics`].
+///
+/// All measurements are totals per render frame, i.e. they include every
+/// rollback frame that ran since the last measurement. A rollback frame counts
+/// as resimulated if its frame number is not higher than the highest one run
+/// so far, otherwise it's predicted.
+///
+/// The frame number is taken from `bevy_ggrs`' `RollbackFrameCount` if
+/// available, or from [`RollFrameCount`](crate::RollFrameCount) otherwise.
+/// Without either, all frames count as predicted.
+///
+/// Depends on the [`RollbackSchedulePlugin`](crate::RollbackSchedulePlugin).
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy::diagnostic::LogDiagnosticsPlugin;
+/// # use bevy_roll_safe::prelude::*;
+/// # use bevy_roll_safe::RollbackDiagnosticsPlugin;
+/// # fn start() {
+/// # let mut app = App::new();
+/// app.add_plugins((
+///     RollbackSchedulePlugin::new(FixedUpdate).with_frame_count(),
+///     RollbackDiagnosticsPlugin,
+///     LogDiagnosticsPlugin::default(),
+/// ));
+/// # }
+/// ```
+pub struct RollbackDiagnosticsPlugin;
+
+impl RollbackDiagnosticsPlugin {
+    /// Time spent in [`RollbackPreUpdate`].
+    pub const PRE_UPDATE: DiagnosticPath = DiagnosticPath::const_new("rollback/pre_update");
+    /// Time spent in [`RollbackStateTransition`].
+    pub const STATE_TRANSITION: DiagnosticPath =
+        DiagnosticPath::const_new("rollback/state_transition");
+    /// Time spent in [`RollbackUpdate`].
+    pub const UPDATE: DiagnosticPath = DiagnosticPath::const_new("rollback/update");
+    /// Time spent in [`RollbackPostUpdate`].
+    pub const POST_UPDATE: DiagnosticPath = DiagnosticPath::const_new("rollback/post_update");
+    /// Number of predicted (new) rollback frames.
+    pub const PREDICTED_FRAMES: DiagnosticPath =
+        DiagnosticPath::const_new("rollback/predicted_frames");
+    /// Number of resimulated rollback frames.
+    pub const RESIMULATED_FRAMES: DiagnosticPath =
+        DiagnosticPath::const_new("rollback/resimulated_frames");
+    /// Time spent running predicted rollback frames.
+    pub const PREDICTED_TIME: DiagnosticPath = DiagnosticPath::const_new("rollback/predicted_time");
+    /// Time spent running resimulated rollback frames.
+    pub const RESIMULATED_TIME: DiagnosticPath =
+        DiagnosticPath::const_new("rollback/resimulated_time");
+}
+
+impl Plugin for RollbackDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        for path in [
+            Self::PRE_UPDATE,
+            Self::STATE_TRANSITION,
+            Self::UPDATE,
+            Self::POST_UPDATE,
+            Self::PREDICTED_TIME,
+            Self::RESIMULATED_TIME,
+        ] {
+            app.register_diagnostic(Diagnostic::new(path).with_suffix("ms"));
+        }
+
+        app.register_diagnostic(Diagnostic::new(Self::PREDICTED_FRAMES))
+            .register_diagnostic(Diagnostic::new(Self::RESIMULATED_FRAMES))
+            .init_resource::<RollbackTimings>()
+            .add_systems(Last, measure_rollback_timings);
+    }
+}
+
+/// Time spent in the rollback schedules since the last measurement.
+///
+/// Filled in by `run_schedules` when present.
+#[derive(Resource, Default, Debug)]
+pub(crate) struct RollbackTimings {
+    phases: HashMap<InternedScheduleLabel, Duration>,
+    predicted_frames: u32,
+    resimulated_frames: u32,
+    predicted: Duration,
+    resimulated: Duration,
+}
+
+impl RollbackTimings {
+    pub(crate) fn record_phase(&mut self, label: InternedScheduleLabel, duration: Duration) {
+        *self.phases.entry(label).or_default() += duration;
+    }
+
+    pub(crate) fn record_frame(&mut self, resimulation: bool, duration: Duration) {
+        if resimulation {
+            self.resimulated_frames += 1;
+            self.resimulated += duration;
+        } else {
+            self.predicted_frames += 1;
+            self.predicted += duration;
+        }
+    }
+}
+
+fn measure_rollback_timings(mut diagnostics: Diagnostics, mut timings: ResMut<RollbackTimings>) {
+    let phases = [
+        (
+            RollbackPreUpdate.intern(),
+            RollbackDiagnosticsPlugin::PRE_UPDATE,
+        ),
+        (
+            RollbackStateTransition.intern(),
+            RollbackDiagnosticsPlugin::STATE_TRANSITION,
+        ),
+        (RollbackUpdate.intern(), RollbackDiagnosticsPlugin::UPDATE),
+        (
+            RollbackPostUpdate.intern(),
+            RollbackDiagnosticsPlugin::POST_UPDATE,
+        ),
+    ];
+
+    for (label, path) in phases {
+        let duration = timings.phases.get(&label).copied().unwrap_or_default();
+        diagnostics.add_measurement(&path, || millis(duration));
+    }
+
+    diagnostics.add_measurement(&RollbackDiagnosticsPlugin::PREDICTED_FRAMES, || {
+        timings.predicted_frames as f64
+    });
+    diagnostics.add_measurement(&RollbackDiagnosticsPlugin::RESIMULATED_FRAMES, || {
+        timings.resimulated_frames as f64
+    });
+    diagnostics.add_measurement(&RollbackDiagnosticsPlugin::PREDICTED_TIME, || {
+        millis(timings.predicted)
+    });
+    diagnostics.add_measurement(&RollbackDiagnosticsPlugin::RESIMULATED_TIME, || {
+        millis(timings.resimulated)
+    });
+
+    *timings = default();
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::TestRollbackSchedule, RollFrameCount, RollbackSchedulePlugin};
+    use bevy::diagnostic::DiagnosticsStore;
+
+    fn latest(app: &App, path: &DiagnosticPath) -> f64 {
+        app.world()
+            .resource::<DiagnosticsStore>()
+            .get(path)
+            .and_then(Diagnostic::value)
+            .unwrap()
+    }
+
+    #[test]
+    fn counts_predicted_and_resimulated_frames() {
+        let mut app = App::new();
+        app.add_plugins((
+            RollbackSchedulePlugin::new(TestRollbackSchedule).with_frame_count(),
+            RollbackDiagnosticsPlugin,
+        ));
+
+        // frames 1 and 2
+        app.world_mut().run_schedule(TestRollbackSchedule);
+        app.world_mut().run_schedule(TestRollbackSchedule);
+        app.world_mut().run_schedule(Last);
+
+        assert_eq!(
+            latest(&app, &RollbackDiagnosticsPlugin::PREDICTED_FRAMES),
+            2.0
+        );
+        assert_eq!(
+            latest(&app, &RollbackDiagnosticsPlugin::RESIMULATED_FRAMES),
+            0.0
+        );
+
+        // roll back to frame 0, resimulate 1 and 2, and predict 3
+        app.world_mut().resource_mut::<RollFrameCount>().0 = 0;
+        app.world_mut().run_schedule(TestRollbackSchedule);
+        app.world_mut().run_schedule(TestRollbackSchedule);
+        app.world_mut().run_schedule(TestRollbackSchedule);
+        app.world_mut().run_schedule(Last);
+
+        assert_eq!(
+            latest(&app, &RollbackDiagnosticsPlugin::PREDICTED_FRAMES),
+            1.0
+        );
+        assert_eq!(
+            latest(&app, &RollbackDiagnosticsPlugin::RESIMULATED_FRAMES),
+            2.0
+        );
+        assert!(latest(&app, &RollbackDiagnosticsPlugin::UPDATE) >= 0.0);
+    }
+}
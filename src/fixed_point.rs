@@ -0,0 +1,474 @@
+//! Fixed-point math and transforms
+//!
+//! Integer arithmetic is deterministic on all platforms, so simulations that
+//! only use [`Fx`] numbers don't need to worry about floating point
+//! determinism at all.
+//!
+//! Simulate using [`RollTransform`], and add the [`RollTransformPlugin`] to
+//! have Bevy's [`Transform`] follow it for rendering.
+
+use std::{
+    fmt,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+use bevy::{prelude::*, transform::TransformSystems};
+
+/// Signed 32.32 fixed-point number
+///
+/// Multiplication rounds towards negative infinity, division towards zero.
+///
+/// Arithmetic wraps around on overflow, in both debug and release builds, so
+/// all builds give the same results. Division by zero panics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect)]
+#[reflect(Hash, PartialEq)]
+pub struct Fx(i64);
+
+impl Fx {
+    const FRAC_BITS: u32 = 32;
+
+    pub const ZERO: Self = Self(0);
+    pub const ONE: Self = Self(1 << Self::FRAC_BITS);
+    pub const HALF: Self = Self(1 << (Self::FRAC_BITS - 1));
+    pub const MIN: Self = Self(i64::MIN);
+    pub const MAX: Self = Self(i64::MAX);
+    /// Smallest positive value
+    pub const EPSILON: Self = Self(1);
+
+    /// Creates a number from its raw bit representation.
+    pub const fn from_bits(bits: i64) -> Self {
+        Self(bits)
+    }
+
+    /// The raw bit representation.
+    pub const fn to_bits(self) -> i64 {
+        self.0
+    }
+
+    pub const fn from_int(n: i32) -> Self {
+        Self((n as i64) << Self::FRAC_BITS)
+    }
+
+    /// `numerator / denominator`, useful for constants such as `0.1`.
+    ///
+    /// Panics if `denominator` is zero.
+    pub const fn from_ratio(numerator: i32, denominator: i32) -> Self {
+        Self((((numerator as i128) << Self::FRAC_BITS) / denominator as i128) as i64)
+    }
+
+    /// Converts from a float, rounding towards zero.
+    ///
+    /// Deterministic, but intended for setup and configuration only.
+    pub fn from_f32(value: f32) -> Self {
+        Self((value as f64 * (1u64 << Self::FRAC_BITS) as f64) as i64)
+    }
+
+    /// Converts to a float, e.g. for rendering.
+    pub fn to_f32(self) -> f32 {
+        (self.0 as f64 / (1u64 << Self::FRAC_BITS) as f64) as f32
+    }
+
+    /// Rounds towards negative infinity.
+    pub const fn floor(self) -> Self {
+        Self(self.0 & !((1 << Self::FRAC_BITS) - 1))
+    }
+
+    /// The integer part, rounded towards negative infinity.
+    pub const fn to_int(self) -> i32 {
+        (self.0 >> Self::FRAC_BITS) as i32
+    }
+
+    /// Absolute value, [`Fx::MIN`] wraps around to itself.
+    pub const fn abs(self) -> Self {
+        Self(self.0.wrapping_abs())
+    }
+
+    pub fn min(self, other: Self) -> Self {
+        Ord::min(self, other)
+    }
+
+    pub fn max(self, other: Self) -> Self {
+        Ord::max(self, other)
+    }
+
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        Ord::clamp(self, min, max)
+    }
+
+    /// Square root, rounded towards zero. Returns zero for negative numbers.
+    pub fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Self::ZERO;
+        }
+        Self(((self.0 as u128) << Self::FRAC_BITS).isqrt() as i64)
+    }
+}
+
+impl fmt::Display for Fx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_f32())
+    }
+}
+
+impl From<i32> for Fx {
+    fn from(n: i32) -> Self {
+        Self::from_int(n)
+    }
+}
+
+impl Add for Fx {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.wrapping_add(rhs.0))
+    }
+}
+
+impl Sub for Fx {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.wrapping_sub(rhs.0))
+    }
+}
+
+impl Mul for Fx {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        // the product always fits in an i128, truncating it to i64 wraps
+        Self(((self.0 as i128 * rhs.0 as i128) >> Self::FRAC_BITS) as i64)
+    }
+}
+
+/// Panics if `rhs` is zero.
+impl Div for Fx {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        // the quotient always fits in an i128, truncating it to i64 wraps
+        Self((((self.0 as i128) << Self::FRAC_BITS) / rhs.0 as i128) as i64)
+    }
+}
+
+impl Neg for Fx {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0.wrapping_neg())
+    }
+}
+
+macro_rules! impl_assign_ops {
+    ($ty:ident, $rhs:ty) => {
+        impl AddAssign<$rhs> for $ty {
+            fn add_assign(&mut self, rhs: $rhs) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl SubAssign<$rhs> for $ty {
+            fn sub_assign(&mut self, rhs: $rhs) {
+                *self = *self - rhs;
+            }
+        }
+    };
+}
+
+impl_assign_ops!(Fx, Fx);
+
+impl MulAssign for Fx {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign for Fx {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+macro_rules! impl_fx_vec {
+    ($vec:ident, $glam:ident, $($field:ident),+) => {
+        impl $vec {
+            pub const ZERO: Self = Self { $($field: Fx::ZERO),+ };
+
+            pub fn dot(self, rhs: Self) -> Fx {
+                Fx::ZERO $(+ self.$field * rhs.$field)+
+            }
+
+            pub fn length_squared(self) -> Fx {
+                self.dot(self)
+            }
+
+            pub fn length(self) -> Fx {
+                self.length_squared().sqrt()
+            }
+
+            pub fn distance(self, rhs: Self) -> Fx {
+                (self - rhs).length()
+            }
+
+            /// Returns a vector with length 1, or zero if the length is zero.
+            pub fn normalize_or_zero(self) -> Self {
+                let length = self.length();
+                if length == Fx::ZERO {
+                    Self::ZERO
+                } else {
+                    Self { $($field: self.$field / length),+ }
+                }
+            }
+
+            pub fn lerp(self, rhs: Self, s: Fx) -> Self {
+                self + (rhs - self) * s
+            }
+
+            /// Converts to a float vector, e.g. for rendering.
+            pub fn to_f32(self) -> $glam {
+                $glam::new($(self.$field.to_f32()),+)
+            }
+
+            /// Converts from a float vector.
+            ///
+            /// Deterministic, but intended for setup and configuration only.
+            pub fn from_f32(v: $glam) -> Self {
+                Self { $($field: Fx::from_f32(v.$field)),+ }
+            }
+        }
+
+        impl Add for $vec {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self { $($field: self.$field + rhs.$field),+ }
+            }
+        }
+
+        impl Sub for $vec {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self { $($field: self.$field - rhs.$field),+ }
+            }
+        }
+
+        impl Mul<Fx> for $vec {
+            type Output = Self;
+
+            fn mul(self, rhs: Fx) -> Self {
+                Self { $($field: self.$field * rhs),+ }
+            }
+        }
+
+        impl Div<Fx> for $vec {
+            type Output = Self;
+
+            fn div(self, rhs: Fx) -> Self {
+                Self { $($field: self.$field / rhs),+ }
+            }
+        }
+
+        impl Neg for $vec {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self { $($field: -self.$field),+ }
+            }
+        }
+
+        impl_assign_ops!($vec, $vec);
+    };
+}
+
+/// 2D vector of [`Fx`] numbers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Hash, PartialEq)]
+pub struct FxVec2 {
+    pub x: Fx,
+    pub y: Fx,
+}
+
+impl FxVec2 {
+    pub const fn new(x: Fx, y: Fx) -> Self {
+        Self { x, y }
+    }
+
+    /// The z component of the 3D cross product
+    pub fn perp_dot(self, rhs: Self) -> Fx {
+        self.x * rhs.y - self.y * rhs.x
+    }
+
+    pub fn extend(self, z: Fx) -> FxVec3 {
+        FxVec3::new(self.x, self.y, z)
+    }
+}
+
+impl_fx_vec!(FxVec2, Vec2, x, y);
+
+/// 3D vector of [`Fx`] numbers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Hash, PartialEq)]
+pub struct FxVec3 {
+    pub x: Fx,
+    pub y: Fx,
+    pub z: Fx,
+}
+
+impl FxVec3 {
+    pub const ONE: Self = Self::new(Fx::ONE, Fx::ONE, Fx::ONE);
+
+    pub const fn new(x: Fx, y: Fx, z: Fx) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn cross(self, rhs: Self) -> Self {
+        Self::new(
+            self.y * rhs.z - self.z * rhs.y,
+            self.z * rhs.x - self.x * rhs.z,
+            self.x * rhs.y - self.y * rhs.x,
+        )
+    }
+
+    pub fn truncate(self) -> FxVec2 {
+        FxVec2::new(self.x, self.y)
+    }
+}
+
+impl_fx_vec!(FxVec3, Vec3, x, y, z);
+
+/// Fixed-point position and scale for rollback simulation.
+///
+/// Copied to [`Transform`] by the [`RollTransformPlugin`] after the rollback
+/// schedules have run. Rotation is not part of the fixed-point transform, and
+/// is left untouched.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component, Hash, PartialEq)]
+#[require(Transform)]
+pub struct RollTransform {
+    pub translation: FxVec3,
+    pub scale: FxVec3,
+}
+
+impl Default for RollTransform {
+    fn default() -> Self {
+        Self {
+            translation: FxVec3::ZERO,
+            scale: FxVec3::ONE,
+        }
+    }
+}
+
+impl RollTransform {
+    pub fn from_translation(translation: FxVec3) -> Self {
+        Self {
+            translation,
+            ..default()
+        }
+    }
+
+    pub fn from_xyz(x: Fx, y: Fx, z: Fx) -> Self {
+        Self::from_translation(FxVec3::new(x, y, z))
+    }
+}
+
+/// Copies [`RollTransform`]s to [`Transform`]s in [`PostUpdate`], which is a
+/// non-rollback presentation step, before transforms are propagated.
+///
+/// When the `bevy_ggrs` feature is enabled, [`RollTransform`] is also
+/// registered for rollback and checksumming.
+pub struct RollTransformPlugin;
+
+impl Plugin for RollTransformPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<RollTransform>().add_systems(
+            PostUpdate,
+            sync_roll_transforms.before(TransformSystems::Propagate),
+        );
+
+        #[cfg(feature = "bevy_ggrs")]
+        {
+            use bevy_ggrs::RollbackApp;
+            app.rollback_component_with_copy::<RollTransform>()
+                .checksum_component_with_hash::<RollTransform>();
+        }
+    }
+}
+
+/// Copies [`RollTransform`] translation and scale to [`Transform`]
+pub fn sync_roll_transforms(
+    mut transforms: Query<(&RollTransform, &mut Transform), Changed<RollTransform>>,
+) {
+    for (roll_transform, mut transform) in &mut transforms {
+        transform.translation = roll_transform.translation.to_f32();
+        transform.scale = roll_transform.scale.to_f32();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fx_arithmetic() {
+        let a = Fx::from_ratio(3, 2);
+        let b = Fx::from_int(-4);
+
+        assert_eq!(a + b, Fx::from_ratio(-5, 2));
+        assert_eq!(a * b, Fx::from_int(-6));
+        assert_eq!(b / a, Fx::from_ratio(-8, 3));
+        assert_eq!(Fx::from_int(9).sqrt(), Fx::from_int(3));
+        assert_eq!(Fx::from_ratio(-1, 2).floor(), Fx::from_int(-1));
+        assert_eq!(Fx::from_ratio(7, 2).to_int(), 3);
+        assert_eq!(Fx::from_f32(0.75), Fx::from_ratio(3, 4));
+        assert_eq!(Fx::from_ratio(3, 4).to_f32(), 0.75);
+    }
+
+    #[test]
+    fn fx_overflow_wraps() {
+        assert_eq!(Fx::MAX + Fx::EPSILON, Fx::MIN);
+        assert_eq!(Fx::MIN - Fx::EPSILON, Fx::MAX);
+        assert_eq!(-Fx::MIN, Fx::MIN);
+        assert_eq!(Fx::MIN.abs(), Fx::MIN);
+        assert_eq!(Fx::MAX * Fx::from_int(2), Fx::from_bits(-2));
+        assert_eq!(Fx::MAX / Fx::HALF, Fx::from_bits(-2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn fx_division_by_zero_panics() {
+        let _ = Fx::ONE / Fx::ZERO;
+    }
+
+    #[test]
+    fn fx_vec_length() {
+        let v = FxVec2::new(Fx::from_int(3), Fx::from_int(-4));
+        assert_eq!(v.length(), Fx::from_int(5));
+        assert_eq!(
+            v.normalize_or_zero(),
+            FxVec2::new(Fx::from_ratio(3, 5), Fx::from_ratio(-4, 5))
+        );
+        assert_eq!(FxVec2::ZERO.normalize_or_zero(), FxVec2::ZERO);
+    }
+
+    #[test]
+    fn syncs_roll_transform() {
+        let mut app = App::new();
+        app.add_plugins(RollTransformPlugin);
+
+        let entity = app
+            .world_mut()
+            .spawn(RollTransform::from_xyz(
+                Fx::from_int(1),
+                Fx::from_ratio(1, 2),
+                Fx::ZERO,
+            ))
+            .id();
+
+        app.update();
+
+        let transform = app.world().get::<Transform>(entity).unwrap();
+        assert_eq!(transform.translation, Vec3::new(1.0, 0.5, 0.0));
+        assert_eq!(transform.scale, Vec3::ONE);
+    }
+}
@@ -13,3 +13,69 @@ pub struct RollFrameCount(pub u32);
 pub fn increase_frame_count(mut frame_count: ResMut<RollFrameCount>) {
     frame_count.0 = frame_count.0.wrapping_add(1);
 }
+
+/// The current rollback frame, read from `bevy_ggrs`' `RollbackFrameCount` if
+/// available, or [`RollFrameCount`] otherwise.
+pub(crate) fn current_rollback_frame(world: &World) -> Option<u32> {
+    #[cfg(feature = "bevy_ggrs")]
+    if let Some(frame) = world.get_resource::<bevy_ggrs::RollbackFrameCount>() {
+        return Some(frame.0 as u32);
+    }
+
+    world.get_resource::<RollFrameCount>().map(|frame| frame.0)
+}
+
+/// Tells new rollback frames from resimulated ones, by tracking the highest
+/// frame run in the current session.
+///
+/// Updated once at the start of each rollback frame by the
+/// [`RollbackSchedulePlugin`](crate::RollbackSchedulePlugin), and reset on
+/// [`RollbackSessionReset`](crate::RollbackSessionReset), since frame numbers
+/// start over in a new session.
+#[derive(Resource, Default, Debug)]
+pub(crate) struct RollbackFrameTracker {
+    current: Option<u32>,
+    highest: Option<u32>,
+    new_frames: u32,
+    in_frame: bool,
+}
+
+impl RollbackFrameTracker {
+    /// Marks the start of a rollback frame.
+    pub(crate) fn begin_frame(&mut self, frame: Option<u32>) {
+        self.current = frame;
+        // without frame numbers, every frame counts as new
+        self.new_frames = match (frame, self.highest) {
+            (Some(frame), Some(highest)) => frame.saturating_sub(highest),
+            _ => 1,
+        };
+        self.highest = self.highest.max(frame);
+        self.in_frame = true;
+    }
+
+    /// Marks the end of a rollback frame.
+    pub(crate) fn end_frame(&mut self) {
+        self.in_frame = false;
+    }
+
+    /// Whether the rollback schedules are currently running.
+    pub(crate) fn in_frame(&self) -> bool {
+        self.in_frame
+    }
+
+    /// The number of the current, or last, rollback frame.
+    pub(crate) fn current(&self) -> Option<u32> {
+        self.current
+    }
+
+    /// How many frames the current frame is past the highest one before it,
+    /// zero when resimulating.
+    pub(crate) fn new_frames(&self) -> u32 {
+        self.new_frames
+    }
+
+    /// Whether the current frame had already been run before.
+    pub(crate) fn is_resimulation(&self) -> bool {
+        self.new_frames == 0
+    }
+}
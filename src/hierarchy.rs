@@ -0,0 +1,167 @@
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+use bevy_ggrs::{
+    AdvanceWorld, AdvanceWorldSystems, ChildOfSnapshotPlugin, LoadWorld, LoadWorldSystems,
+    Rollback, RollbackOrdered,
+};
+
+/// Keeps [`Children`] of rollback entities in a deterministic order.
+///
+/// `bevy_ggrs` restores [`ChildOf`] relationships when loading a snapshot,
+/// but [`Children`] are rebuilt in whichever order the relationships happen
+/// to be re-inserted, so iterating over them may give a different order after
+/// a rollback than during the original simulation.
+///
+/// This plugin sorts [`Children`] by rollback id after each rollback frame and
+/// after each snapshot load. Children without `Rollback` are placed last, in
+/// their current order. In debug builds, [`ChildOf`] and [`Children`] are also
+/// checked for consistency after loading.
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_ggrs::prelude::*;
+/// # use bevy_roll_safe::RollHierarchyPlugin;
+/// # fn start() {
+/// # let mut app = App::new();
+/// app.add_plugins(RollHierarchyPlugin);
+/// # }
+/// ```
+pub struct RollHierarchyPlugin;
+
+impl Plugin for RollHierarchyPlugin {
+    fn build(&self, app: &mut App) {
+        // already added by `SnapshotPlugin`
+        if !app.is_plugin_added::<ChildOfSnapshotPlugin>() {
+            app.add_plugins(ChildOfSnapshotPlugin);
+        }
+
+        app.add_systems(
+            AdvanceWorld,
+            sort_rollback_children.in_set(AdvanceWorldSystems::Last),
+        )
+        .add_systems(
+            LoadWorld,
+            sort_rollback_children.after(LoadWorldSystems::Mapping),
+        );
+
+        #[cfg(debug_assertions)]
+        app.add_systems(LoadWorld, validate_hierarchy.after(sort_rollback_children));
+    }
+}
+
+/// Sorts [`Children`] of entities that have at least one rollback child.
+pub fn sort_rollback_children(
+    mut parents: Query<&mut Children>,
+    rollback: Query<&Rollback>,
+    rollback_ordered: Res<RollbackOrdered>,
+) {
+    let order = |entity: &Entity| {
+        rollback
+            .get(*entity)
+            .ok()
+            .map(|rollback| rollback_ordered.order(*rollback))
+    };
+
+    for mut children in &mut parents {
+        if !children.iter().any(|child| rollback.contains(child)) {
+            continue;
+        }
+
+        let sorted = children
+            .windows(2)
+            .all(|pair| compare(order(&pair[0]), order(&pair[1])) != Ordering::Greater);
+
+        // avoid triggering change detection if the order is already correct
+        if !sorted {
+            children.sort_by(|a, b| compare(order(a), order(b)));
+        }
+    }
+}
+
+/// Rollback entities first, by rollback order.
+fn compare(a: Option<u64>, b: Option<u64>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(debug_assertions)]
+fn validate_hierarchy(
+    children: Query<(Entity, &Children)>,
+    child_of: Query<(Entity, &ChildOf), With<Rollback>>,
+) {
+    for (entity, child_of) in &child_of {
+        let parent = child_of.parent();
+        let listed = children
+            .get(parent)
+            .is_ok_and(|(_, children)| children.contains(&entity));
+        if !listed {
+            error!("Rollback entity {entity} has parent {parent}, but is not one of its children");
+        }
+    }
+
+    for (parent, parent_children) in &children {
+        for &child in parent_children {
+            if let Ok((_, child_of)) = child_of.get(child) {
+                if child_of.parent() != parent {
+                    error!(
+                        "Rollback entity {child} is a child of {parent}, but has parent {}",
+                        child_of.parent()
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::advance_and_save;
+    use bevy_ggrs::{AddRollbackCommandExtension, RollbackFrameCount, SaveWorld, SnapshotPlugin};
+
+    fn children(world: &mut World, parent: Entity) -> Vec<Entity> {
+        world.get::<Children>(parent).unwrap().to_vec()
+    }
+
+    #[test]
+    fn sorts_children_by_rollback_order() {
+        let mut app = App::new();
+        app.add_plugins((SnapshotPlugin, RollHierarchyPlugin));
+
+        let world = app.world_mut();
+        let parent = world.commands().spawn_empty().add_rollback().id();
+        let a = world.commands().spawn_empty().add_rollback().id();
+        let b = world.commands().spawn_empty().add_rollback().id();
+        world.flush();
+
+        world.entity_mut(b).insert(ChildOf(parent));
+        world.entity_mut(a).insert(ChildOf(parent));
+        assert_eq!(children(world, parent), vec![b, a]);
+
+        world.run_schedule(SaveWorld);
+        advance_and_save(world);
+        assert_eq!(children(world, parent), vec![a, b]);
+
+        // a is respawned on load, and added to the end of `Children`
+        let a_rollback = *world.get::<Rollback>(a).unwrap();
+        world.despawn(a);
+        world.resource_mut::<RollbackFrameCount>().0 = 1;
+        world.run_schedule(LoadWorld);
+
+        let respawned_a = world
+            .query::<(Entity, &Rollback)>()
+            .iter(world)
+            .find_map(|(entity, rollback)| (*rollback == a_rollback).then_some(entity))
+            .unwrap();
+        assert_ne!(respawned_a, a);
+        assert_eq!(children(world, parent), vec![respawned_a, b]);
+        for child in [respawned_a, b] {
+            assert_eq!(world.get::<ChildOf>(child).unwrap().parent(), parent);
+        }
+    }
+}
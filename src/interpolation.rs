@@ -0,0 +1,393 @@
+use std::{marker::PhantomData, time::Duration};
+
+use bevy::{prelude::*, transform::TransformSystems};
+
+use crate::{frame_count::RollbackFrameTracker, RollCommandsSystems, RollbackPostUpdate};
+
+/// Components that can be blended between two rollback frames by the
+/// [`RollbackInterpolationPlugin`].
+pub trait RollInterpolate: Component<Mutability = bevy::ecs::component::Mutable> + Clone {
+    /// Blends between `self` (the previous frame) and `next` (the current
+    /// frame), where `s` is in `0.0..=1.0`.
+    fn interpolate(&self, next: &Self, s: f32) -> Self;
+}
+
+impl RollInterpolate for Transform {
+    fn interpolate(&self, next: &Self, s: f32) -> Self {
+        Transform {
+            translation: self.translation.lerp(next.translation, s),
+            rotation: self.rotation.slerp(next.rotation, s),
+            scale: self.scale.lerp(next.scale, s),
+        }
+    }
+}
+
+/// Opts an entity in to interpolation of `T` by the
+/// [`RollbackInterpolationPlugin<T>`].
+///
+/// Holds the values of `T` at the end of the two most recent rollback frames.
+/// It's presentation state and should not be registered for rollback.
+///
+/// As a consequence, it's lost when `bevy_ggrs` respawns an entity that was
+/// despawned after the loaded snapshot, since only components registered for
+/// rollback are restored. Insert it from an observer on one of those
+/// components, or make it a required component, to keep interpolating such
+/// entities.
+#[derive(Component, Debug, Clone)]
+pub struct RollInterpolated<T: RollInterpolate = Transform> {
+    previous: Option<T>,
+    current: Option<T>,
+    /// Whether `T` currently holds a blended value that must be restored
+    blended: bool,
+}
+
+impl<T: RollInterpolate> Default for RollInterpolated<T> {
+    fn default() -> Self {
+        Self {
+            previous: None,
+            current: None,
+            blended: false,
+        }
+    }
+}
+
+impl<T: RollInterpolate> RollInterpolated<T> {
+    /// The value at the end of the second most recent rollback frame.
+    pub fn previous(&self) -> Option<&T> {
+        self.previous.as_ref()
+    }
+
+    /// The value at the end of the most recent rollback frame.
+    pub fn current(&self) -> Option<&T> {
+        self.current.as_ref()
+    }
+}
+
+/// Disables interpolation for an entity for the current rollback frame.
+///
+/// Insert it from rollback systems when teleporting an entity, so it snaps to
+/// its new position instead of sliding there. It's removed automatically at
+/// the end of [`RollbackPostUpdate`].
+#[derive(Component, Default, Debug, Clone, Copy)]
+pub struct RollTeleport;
+
+/// Keeps track of how far rendering is between the two most recent rollback
+/// frames.
+///
+/// Mirrors the fixed timestep accumulator of the rollback schedule: time is
+/// added every render frame, and one timestep is subtracted for each *new*
+/// rollback frame, so resimulated frames don't affect it.
+#[derive(Resource, Debug, Default)]
+pub struct RollInterpolationTime {
+    timestep: Duration,
+    overstep: Duration,
+    new_frames: u32,
+}
+
+impl RollInterpolationTime {
+    /// The duration of a rollback frame.
+    pub fn timestep(&self) -> Duration {
+        self.timestep
+    }
+
+    /// How far rendering is between the previous and current rollback frame,
+    /// in `0.0..=1.0`.
+    pub fn overstep_fraction(&self) -> f32 {
+        if self.timestep.is_zero() {
+            return 1.0;
+        }
+        self.overstep.as_secs_f32() / self.timestep.as_secs_f32()
+    }
+}
+
+/// System set containing the systems that store values of interpolated
+/// components at the end of [`RollbackPostUpdate`].
+///
+/// Systems in [`RollbackPostUpdate`] that modify interpolated components should
+/// be ordered before this set.
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RollInterpolationSystems;
+
+/// Smooths rendering of `T` when the rollback schedule runs at a lower rate
+/// than rendering.
+///
+/// At the end of each rollback frame, the value of `T` is stored in
+/// [`RollInterpolated<T>`]. In [`PostUpdate`], `T` is then set to a blend of
+/// the two most recent rollback frames, and restored in [`First`], before any
+/// rollback systems run.
+///
+/// Only entities with two stored frames are blended, and only blended values
+/// are restored, so `T` is left alone otherwise. Blending does write to `T`
+/// outside the rollback schedules though, so Bevy's [`Changed<T>`] matches
+/// interpolated entities every render frame, and writes to `T` made between
+/// [`PostUpdate`] and [`First`] are overwritten. Use
+/// [`RollChanged<T>`](crate::RollChanged), which ignores writes made between
+/// rollback frames, in rollback systems.
+///
+/// Rollbacks simply overwrite the stored values with the resimulated ones, so
+/// corrections are picked up immediately. Insert [`RollTeleport`] to skip
+/// blending for a frame.
+///
+/// Depends on the [`RollbackSchedulePlugin`](crate::RollbackSchedulePlugin).
+/// The rollback frame duration is read from `bevy_ggrs`' `RollbackFrameRate`
+/// if present, or [`Time<Fixed>`] otherwise.
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_roll_safe::prelude::*;
+/// # use bevy_roll_safe::{RollInterpolated, RollbackInterpolationPlugin};
+/// # fn start() {
+/// # let mut app = App::new();
+/// app.add_plugins((
+///     RollbackSchedulePlugin::new(FixedUpdate),
+///     RollbackInterpolationPlugin::<Transform>::default(),
+/// ));
+///
+/// app.world_mut()
+///     .spawn((Transform::default(), RollInterpolated::<Transform>::default()));
+/// # }
+/// ```
+pub struct RollbackInterpolationPlugin<T: RollInterpolate = Transform>(PhantomData<T>);
+
+impl<T: RollInterpolate> Default for RollbackInterpolationPlugin<T> {
+    fn default() -> Self {
+        Self(default())
+    }
+}
+
+impl<T: RollInterpolate> Plugin for RollbackInterpolationPlugin<T> {
+    fn build(&self, app: &mut App) {
+        // shared between all interpolated components
+        if !app.world().contains_resource::<RollInterpolationTime>() {
+            app.init_resource::<RollInterpolationTime>()
+                .add_systems(
+                    RollbackPostUpdate,
+                    (
+                        count_rollback_frames.in_set(RollInterpolationSystems),
+                        remove_teleports
+                            .after(RollInterpolationSystems)
+                            .in_set(RollCommandsSystems),
+                    ),
+                )
+                .add_systems(
+                    PostUpdate,
+                    update_overstep.before(TransformSystems::Propagate),
+                );
+        }
+
+        app.add_systems(First, restore_current::<T>)
+            .add_systems(
+                RollbackPostUpdate,
+                store_current::<T>.in_set(RollInterpolationSystems),
+            )
+            .add_systems(
+                PostUpdate,
+                interpolate::<T>
+                    .after(update_overstep)
+                    .before(TransformSystems::Propagate),
+            );
+    }
+}
+
+fn count_rollback_frames(
+    mut time: ResMut<RollInterpolationTime>,
+    tracker: Res<RollbackFrameTracker>,
+) {
+    time.new_frames += tracker.new_frames();
+}
+
+fn update_overstep(
+    mut interpolation_time: ResMut<RollInterpolationTime>,
+    time: Res<Time>,
+    fixed_time: Option<Res<Time<Fixed>>>,
+    #[cfg(feature = "bevy_ggrs")] frame_rate: Option<Res<bevy_ggrs::RollbackFrameRate>>,
+) {
+    let timestep = fixed_time.map_or(Time::<Fixed>::default().timestep(), |fixed| {
+        fixed.timestep()
+    });
+    #[cfg(feature = "bevy_ggrs")]
+    let timestep = frame_rate.map_or(timestep, |fps| Duration::from_secs_f64(1.0 / fps.0 as f64));
+
+    let interpolation_time = &mut *interpolation_time;
+    let advanced = timestep * std::mem::take(&mut interpolation_time.new_frames);
+    interpolation_time.timestep = timestep;
+    interpolation_time.overstep = (interpolation_time.overstep + time.delta())
+        .saturating_sub(advanced)
+        .min(timestep);
+}
+
+fn store_current<T: RollInterpolate>(
+    mut query: Query<(&T, &mut RollInterpolated<T>, Has<RollTeleport>)>,
+) {
+    for (value, mut interpolated, teleported) in &mut query {
+        let previous = interpolated.current.replace(value.clone());
+        interpolated.previous = if teleported { None } else { previous };
+    }
+}
+
+fn remove_teleports(mut commands: Commands, teleported: Query<Entity, With<RollTeleport>>) {
+    for entity in &teleported {
+        commands.entity(entity).remove::<RollTeleport>();
+    }
+}
+
+pub(crate) fn restore_current<T: RollInterpolate>(
+    mut query: Query<(&mut T, &mut RollInterpolated<T>)>,
+) {
+    for (mut value, mut interpolated) in &mut query {
+        if !std::mem::take(&mut interpolated.blended) {
+            continue;
+        }
+        if let Some(current) = &interpolated.current {
+            *value = current.clone();
+        }
+    }
+}
+
+pub(crate) fn interpolate<T: RollInterpolate>(
+    time: Res<RollInterpolationTime>,
+    mut query: Query<(&mut T, &mut RollInterpolated<T>)>,
+) {
+    let s = time.overstep_fraction();
+    for (mut value, mut interpolated) in &mut query {
+        let interpolated = &mut *interpolated;
+        if let (Some(previous), Some(current)) = (&interpolated.previous, &interpolated.current) {
+            *value = previous.interpolate(current, s);
+            interpolated.blended = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RollbackSchedulePlugin;
+    use bevy::time::TimeUpdateStrategy;
+
+    #[derive(Component)]
+    struct Velocity(f32);
+
+    fn movement(mut query: Query<(&mut Transform, &Velocity)>) {
+        for (mut transform, velocity) in &mut query {
+            transform.translation.x += velocity.0;
+        }
+    }
+
+    #[test]
+    fn interpolates_between_frames() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            RollbackSchedulePlugin::new(FixedUpdate),
+            RollbackInterpolationPlugin::<Transform>::default(),
+        ))
+        .insert_resource(Time::<Fixed>::from_hz(10.0))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            25,
+        )))
+        .add_systems(crate::RollbackUpdate, movement);
+
+        let entity = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                Velocity(1.0),
+                RollInterpolated::<Transform>::default(),
+            ))
+            .id();
+
+        let x = |app: &App| app.world().get::<Transform>(entity).unwrap().translation.x;
+
+        // the first update has a zero delta, then 4 updates per rollback frame
+        for _ in 0..9 {
+            app.update();
+        }
+        let interpolated = app.world().get::<RollInterpolated>(entity).unwrap();
+        assert_eq!(interpolated.previous().unwrap().translation.x, 1.0);
+        assert_eq!(interpolated.current().unwrap().translation.x, 2.0);
+        assert_eq!(x(&app), 1.0);
+
+        app.update();
+        assert_eq!(x(&app), 1.25);
+
+        app.update();
+        assert_eq!(x(&app), 1.5);
+
+        // teleports are not interpolated
+        app.world_mut().entity_mut(entity).insert(RollTeleport);
+        app.update();
+        app.update();
+        assert_eq!(x(&app), 3.0);
+        assert!(app.world().get::<RollTeleport>(entity).is_none());
+    }
+
+    #[test]
+    fn only_writes_blended_values() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            RollbackSchedulePlugin::new(FixedUpdate),
+            RollbackInterpolationPlugin::<Transform>::default(),
+        ))
+        .insert_resource(Time::<Fixed>::from_hz(10.0))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            25,
+        )));
+
+        let entity = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                RollInterpolated::<Transform>::default(),
+            ))
+            .id();
+
+        // run the first rollback frame, a single stored frame can't be blended
+        for _ in 0..5 {
+            app.update();
+        }
+        let interpolated = app.world().get::<RollInterpolated>(entity).unwrap();
+        assert!(interpolated.current().is_some());
+        assert!(interpolated.previous().is_none());
+
+        // so writes outside the rollback schedules are kept
+        app.world_mut()
+            .get_mut::<Transform>(entity)
+            .unwrap()
+            .translation
+            .x = 5.0;
+        app.update();
+        assert_eq!(
+            app.world().get::<Transform>(entity).unwrap().translation.x,
+            5.0
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bevy_ggrs")]
+    fn session_reset_counts_new_frames() {
+        use crate::RollbackSessionReset;
+        use bevy_ggrs::RollbackFrameCount;
+
+        let mut app = App::new();
+        app.add_plugins((
+            RollbackSchedulePlugin::new(FixedUpdate),
+            RollbackInterpolationPlugin::<Transform>::default(),
+        ))
+        .insert_resource(RollbackFrameCount(10));
+
+        app.world_mut().run_schedule(FixedUpdate);
+        assert_eq!(
+            app.world().resource::<RollInterpolationTime>().new_frames,
+            1
+        );
+
+        app.world_mut().trigger(RollbackSessionReset);
+        app.world_mut().resource_mut::<RollbackFrameCount>().0 = 1;
+        app.world_mut().run_schedule(FixedUpdate);
+        assert_eq!(
+            app.world().resource::<RollInterpolationTime>().new_frames,
+            2
+        );
+    }
+}
@@ -2,12 +2,46 @@
 
 use std::marker::PhantomData;
 
-use bevy::{ecs::schedule::ScheduleLabel, prelude::*, state::state::FreelyMutableState};
+use bevy::{
+    ecs::{
+        schedule::{InternedScheduleLabel, ScheduleLabel},
+        system::ScheduleSystem,
+    },
+    prelude::*,
+    state::state::FreelyMutableState,
+};
 
 #[cfg(feature = "audio")]
 mod audio;
+mod budget;
+mod change_detection;
+mod commands_check;
+#[cfg(feature = "bevy_ggrs")]
+mod desync;
+mod diagnostics;
+#[cfg(feature = "fixed_point")]
+pub mod fixed_point;
 mod frame_count;
+#[cfg(feature = "bevy_ggrs")]
+mod hierarchy;
+mod interpolation;
+mod lifetime;
+mod local;
+#[cfg(feature = "math_determinism")]
+pub mod math;
+mod ordering;
+mod propagation;
 mod schedule;
+mod session;
+#[cfg(feature = "bevy_ggrs")]
+mod smoothing;
+mod state_history;
+mod state_option;
+mod state_scoped;
+#[cfg(feature = "bevy_ggrs")]
+mod strategy;
+#[cfg(test)]
+mod test_utils;
 
 // re-exports
 #[cfg(feature = "audio")]
@@ -15,15 +49,47 @@ pub use audio::{
     remove_finished_sounds, start_rollback_sounds, sync_rollback_sounds, RollbackAudioPlayer,
     RollbackAudioPlayerInstance, RollbackAudioPlugin,
 };
+pub use budget::{rollback_budget_allows_presentation, RollbackBudget, RollbackBudgetPolicy};
+pub use change_detection::{
+    RollAdded, RollAddedFlag, RollChangeDetectionPlugin, RollChangeDetectionSystems, RollChanged,
+    RollChangedFlag,
+};
+pub use commands_check::RollCommandsSystems;
+#[cfg(feature = "bevy_ggrs")]
+pub use desync::{DesyncDetector, DesyncDetectorPlugin, DesyncReport, DesyncSource};
+pub use diagnostics::RollbackDiagnosticsPlugin;
 pub use frame_count::{increase_frame_count, RollFrameCount};
+#[cfg(feature = "bevy_ggrs")]
+pub use hierarchy::{sort_rollback_children, RollHierarchyPlugin};
+pub use interpolation::{
+    RollInterpolate, RollInterpolated, RollInterpolationSystems, RollInterpolationTime,
+    RollTeleport, RollbackInterpolationPlugin,
+};
+pub use lifetime::{
+    tick_lifetimes, RollLifetime, RollLifetimeExpired, RollLifetimePlugin, RollLifetimeSystems,
+};
+pub use local::{RollLocal, RollLocalValue, RollLocals};
+pub use ordering::{RollOrder, RollQuery};
+pub use propagation::{
+    propagate_roll_transforms, RollTransformPropagationPlugin, RollTransformPropagationSystems,
+};
 pub use schedule::{
     RollbackPostUpdate, RollbackPreUpdate, RollbackSchedulePlugin, RollbackStateTransition,
     RollbackUpdate,
 };
+pub use session::RollbackSessionReset;
+#[cfg(feature = "bevy_ggrs")]
+pub use smoothing::{RollbackSmoothing, RollbackSmoothingPlugin};
+pub use state_history::{RollStateHistory, RollStateTransitionRecord};
+pub use state_option::{apply_state_option_transition, RollStateOption};
+pub use state_scoped::{RollDespawnOnEnter, RollDespawnOnExit};
+#[cfg(feature = "bevy_ggrs")]
+pub use strategy::{FromReflectStrategy, NextStateStrategy, StateStrategy};
 
 pub mod prelude {
     pub use super::{
-        RollApp, RollbackPostUpdate, RollbackPreUpdate, RollbackSchedulePlugin,
+        RollAdded, RollApp, RollChangeDetectionPlugin, RollChanged, RollLocal, RollOrder,
+        RollQuery, RollbackPostUpdate, RollbackPreUpdate, RollbackSchedulePlugin,
         RollbackStateTransition, RollbackUpdate,
     };
     #[cfg(feature = "audio")]
@@ -32,6 +98,14 @@ pub mod prelude {
 
 pub trait RollApp {
     /// Init state transitions in the given schedule
+    ///
+    /// Can be called again with another schedule, e.g. a menu schedule outside
+    /// of rollback, to also run the state's transitions there.
+    ///
+    /// Panics if the state was already initialized with Bevy's `init_state`
+    /// or `insert_state`. When Bevy initializes it after this, the panic
+    /// happens at startup instead, except for `init_state` of a state that
+    /// already exists, which Bevy only warns about.
     fn init_roll_state_in_schedule<S: States + FromWorld + FreelyMutableState>(
         &mut self,
         schedule: impl ScheduleLabel,
@@ -40,16 +114,105 @@ pub trait RollApp {
     /// Init state transitions in the given schedule
     fn init_roll_state<S: States + FromWorld + FreelyMutableState>(&mut self) -> &mut Self;
 
+    /// Init state transitions in the given schedule, starting in the given
+    /// state
+    fn insert_roll_state_in_schedule<S: States + FreelyMutableState>(
+        &mut self,
+        initial: S,
+        schedule: impl ScheduleLabel,
+    ) -> &mut Self;
+
+    /// Init state transitions in [`RollbackStateTransition`], starting in the
+    /// given state
+    fn insert_roll_state<S: States + FreelyMutableState>(&mut self, initial: S) -> &mut Self;
+
     #[cfg(feature = "bevy_ggrs")]
-    /// Register this state to be rolled back by bevy_ggrs
+    /// Register this state to be rolled back and checksummed by bevy_ggrs
     fn init_ggrs_state<S: States + FromWorld + Clone + FreelyMutableState>(&mut self) -> &mut Self;
 
     #[cfg(feature = "bevy_ggrs")]
-    /// Register this state to be rolled back by bevy_ggrs in the specified schedule
+    /// Register this state to be rolled back and checksummed by bevy_ggrs in the specified schedule
     fn init_ggrs_state_in_schedule<S: States + FromWorld + Clone + FreelyMutableState>(
         &mut self,
         schedule: impl ScheduleLabel,
     ) -> &mut Self;
+
+    #[cfg(feature = "bevy_ggrs")]
+    /// Register this state to be rolled back and checksummed by bevy_ggrs,
+    /// starting in the given state
+    fn insert_ggrs_state<S: States + FreelyMutableState>(&mut self, initial: S) -> &mut Self;
+
+    #[cfg(feature = "bevy_ggrs")]
+    /// Register this state to be rolled back and checksummed by bevy_ggrs in
+    /// the specified schedule, starting in the given state
+    fn insert_ggrs_state_in_schedule<S: States + FreelyMutableState>(
+        &mut self,
+        initial: S,
+        schedule: impl ScheduleLabel,
+    ) -> &mut Self;
+
+    /// Init transitions of an optional state, which may be absent, in the
+    /// given schedule
+    ///
+    /// Transitions are queued with [`RollStateOption<S>`] instead of
+    /// [`NextState<S>`].
+    fn insert_roll_state_option_in_schedule<S: States + FreelyMutableState>(
+        &mut self,
+        initial: Option<S>,
+        schedule: impl ScheduleLabel,
+    ) -> &mut Self;
+
+    /// Init transitions of an optional state, which may be absent, in
+    /// [`RollbackStateTransition`]
+    fn insert_roll_state_option<S: States + FreelyMutableState>(
+        &mut self,
+        initial: Option<S>,
+    ) -> &mut Self;
+
+    #[cfg(feature = "bevy_ggrs")]
+    /// Register this optional state to be rolled back and checksummed by
+    /// bevy_ggrs
+    fn insert_ggrs_state_option<S: States + FreelyMutableState>(
+        &mut self,
+        initial: Option<S>,
+    ) -> &mut Self;
+
+    #[cfg(feature = "bevy_ggrs")]
+    /// Register this optional state to be rolled back and checksummed by
+    /// bevy_ggrs in the specified schedule
+    fn insert_ggrs_state_option_in_schedule<S: States + FreelyMutableState>(
+        &mut self,
+        initial: Option<S>,
+        schedule: impl ScheduleLabel,
+    ) -> &mut Self;
+
+    /// Insert the resource `R` when the rollback state `S` enters `state`, and
+    /// remove it when it exits it
+    ///
+    /// The resource is created with [`FromWorld`] in `OnEnter(state)`, and
+    /// removed in `OnExit(state)`, so it's only present while the state is
+    /// active.
+    ///
+    /// The resource isn't rolled back, so in bevy_ggrs sessions it may be
+    /// missing after a rollback across `OnExit(state)`. Use
+    /// [`RollApp::add_ggrs_state_scoped_resource`] there instead.
+    fn add_roll_state_scoped_resource<S: States, R: Resource + FromWorld>(
+        &mut self,
+        state: S,
+    ) -> &mut Self;
+
+    #[cfg(feature = "bevy_ggrs")]
+    /// Like [`RollApp::add_roll_state_scoped_resource`], but also registers
+    /// the resource to be rolled back by bevy_ggrs, including whether it exists
+    fn add_ggrs_state_scoped_resource<S: States, R: Resource + FromWorld + Clone>(
+        &mut self,
+        state: S,
+    ) -> &mut Self;
+
+    #[cfg(feature = "bevy_ggrs")]
+    /// Register a resource that implements [`FromReflect`], but not `Clone` or
+    /// [`FromWorld`], to be rolled back by bevy_ggrs using [`FromReflectStrategy`]
+    fn rollback_resource_with_from_reflect<R: Resource + FromReflect>(&mut self) -> &mut Self;
 }
 
 impl RollApp for App {
@@ -57,46 +220,42 @@ impl RollApp for App {
         &mut self,
         schedule: impl ScheduleLabel,
     ) -> &mut Self {
-        if !self.world().contains_resource::<State<S>>() {
-            self.init_resource::<State<S>>()
-                .init_resource::<NextState<S>>()
-                .init_resource::<InitialStateEntered<S>>()
-                // .add_event::<StateTransitionEvent<S>>()
-                .add_systems(
-                    schedule,
-                    (
-                        run_enter_schedule::<S>
-                            .run_if(resource_equals(InitialStateEntered::<S>(false, default()))),
-                        mark_state_initialized::<S>
-                            .run_if(resource_equals(InitialStateEntered::<S>(false, default()))),
-                        apply_state_transition::<S>,
-                    )
-                        .chain(),
-                );
-        } else {
-            let name = std::any::type_name::<S>();
-            warn!("State {} is already initialized.", name);
-        }
-
-        self
+        let registered = self
+            .world()
+            .get_resource::<RollStateRegistration<S>>()
+            .and_then(|registration| registration.initial.clone());
+        let initial = match registered {
+            Some(initial) => initial,
+            None => S::from_world(self.world_mut()),
+        };
+        self.insert_roll_state_in_schedule(initial, schedule)
     }
 
     fn init_roll_state<S: States + FromWorld + FreelyMutableState>(&mut self) -> &mut Self {
         self.init_roll_state_in_schedule::<S>(RollbackStateTransition)
     }
 
+    fn insert_roll_state_in_schedule<S: States + FreelyMutableState>(
+        &mut self,
+        initial: S,
+        schedule: impl ScheduleLabel,
+    ) -> &mut Self {
+        add_roll_state(
+            self,
+            Some(initial),
+            false,
+            schedule.intern(),
+            apply_state_transition::<S>,
+        )
+    }
+
+    fn insert_roll_state<S: States + FreelyMutableState>(&mut self, initial: S) -> &mut Self {
+        self.insert_roll_state_in_schedule(initial, RollbackStateTransition)
+    }
+
     #[cfg(feature = "bevy_ggrs")]
     fn init_ggrs_state<S: States + FromWorld + Clone + FreelyMutableState>(&mut self) -> &mut Self {
-        // verify the schedule exists first?
-        self.get_schedule(RollbackStateTransition)
-            .unwrap_or_else(|| {
-                panic!(
-                    "RollbackStateTransition schedule does not exist. \
-                     Please add it by adding the `RollbackSchedulePlugin` \
-                     or call `init_ggrs_state_in_schedule` with the desired schedule."
-                )
-            });
-
+        expect_state_transition_schedule(self);
         self.init_ggrs_state_in_schedule::<S>(RollbackStateTransition)
     }
 
@@ -105,65 +264,245 @@ impl RollApp for App {
         &mut self,
         schedule: impl ScheduleLabel,
     ) -> &mut Self {
-        use crate::ggrs_support::{NextStateStrategy, StateStrategy};
-        use bevy_ggrs::{CloneStrategy, ResourceSnapshotPlugin};
+        self.init_roll_state_in_schedule::<S>(schedule);
+        register_ggrs_state::<S>(self)
+    }
 
-        self.init_roll_state_in_schedule::<S>(schedule)
-            .add_plugins((
-                ResourceSnapshotPlugin::<StateStrategy<S>>::default(),
-                ResourceSnapshotPlugin::<NextStateStrategy<S>>::default(),
-                ResourceSnapshotPlugin::<CloneStrategy<InitialStateEntered<S>>>::default(),
-            ))
+    #[cfg(feature = "bevy_ggrs")]
+    fn insert_ggrs_state<S: States + FreelyMutableState>(&mut self, initial: S) -> &mut Self {
+        expect_state_transition_schedule(self);
+        self.insert_ggrs_state_in_schedule(initial, RollbackStateTransition)
     }
-}
 
-#[cfg(feature = "bevy_ggrs")]
-mod ggrs_support {
-    use bevy::{prelude::*, state::state::FreelyMutableState};
-    use bevy_ggrs::Strategy;
-    use std::marker::PhantomData;
+    #[cfg(feature = "bevy_ggrs")]
+    fn insert_ggrs_state_in_schedule<S: States + FreelyMutableState>(
+        &mut self,
+        initial: S,
+        schedule: impl ScheduleLabel,
+    ) -> &mut Self {
+        self.insert_roll_state_in_schedule(initial, schedule);
+        register_ggrs_state::<S>(self)
+    }
 
-    pub(crate) struct StateStrategy<S: States>(PhantomData<S>);
+    fn insert_roll_state_option_in_schedule<S: States + FreelyMutableState>(
+        &mut self,
+        initial: Option<S>,
+        schedule: impl ScheduleLabel,
+    ) -> &mut Self {
+        add_roll_state(
+            self,
+            initial,
+            true,
+            schedule.intern(),
+            apply_state_option_transition::<S>,
+        )
+    }
 
-    // todo: make State<S> implement clone instead
-    impl<S: States> Strategy for StateStrategy<S> {
-        type Target = State<S>;
-        type Stored = S;
+    fn insert_roll_state_option<S: States + FreelyMutableState>(
+        &mut self,
+        initial: Option<S>,
+    ) -> &mut Self {
+        self.insert_roll_state_option_in_schedule(initial, RollbackStateTransition)
+    }
 
-        fn store(target: &Self::Target) -> Self::Stored {
-            target.get().to_owned()
-        }
+    #[cfg(feature = "bevy_ggrs")]
+    fn insert_ggrs_state_option<S: States + FreelyMutableState>(
+        &mut self,
+        initial: Option<S>,
+    ) -> &mut Self {
+        expect_state_transition_schedule(self);
+        self.insert_ggrs_state_option_in_schedule(initial, RollbackStateTransition)
+    }
 
-        fn load(stored: &Self::Stored) -> Self::Target {
-            State::new(stored.to_owned())
-        }
+    #[cfg(feature = "bevy_ggrs")]
+    fn insert_ggrs_state_option_in_schedule<S: States + FreelyMutableState>(
+        &mut self,
+        initial: Option<S>,
+        schedule: impl ScheduleLabel,
+    ) -> &mut Self {
+        self.insert_roll_state_option_in_schedule(initial, schedule);
+        register_ggrs_state::<S>(self)
     }
 
-    pub(crate) struct NextStateStrategy<S: States>(PhantomData<S>);
+    fn add_roll_state_scoped_resource<S: States, R: Resource + FromWorld>(
+        &mut self,
+        state: S,
+    ) -> &mut Self {
+        self.add_systems(OnEnter(state.clone()), insert_state_scoped_resource::<R>)
+            .add_systems(OnExit(state), remove_state_scoped_resource::<R>)
+    }
 
-    // todo: make NextState<S> implement clone instead
-    impl<S: States + FreelyMutableState> Strategy for NextStateStrategy<S> {
-        type Target = NextState<S>;
-        type Stored = Option<S>;
+    #[cfg(feature = "bevy_ggrs")]
+    fn add_ggrs_state_scoped_resource<S: States, R: Resource + FromWorld + Clone>(
+        &mut self,
+        state: S,
+    ) -> &mut Self {
+        use bevy_ggrs::RollbackApp;
 
-        fn store(target: &Self::Target) -> Self::Stored {
-            match target {
-                NextState::Unchanged => None,
-                NextState::Pending(s) => Some(s.to_owned()),
-            }
+        self.add_roll_state_scoped_resource::<S, R>(state)
+            .rollback_resource_with_clone::<R>()
+    }
+
+    #[cfg(feature = "bevy_ggrs")]
+    fn rollback_resource_with_from_reflect<R: Resource + FromReflect>(&mut self) -> &mut Self {
+        use bevy_ggrs::ResourceSnapshotPlugin;
+
+        self.add_plugins(ResourceSnapshotPlugin::<FromReflectStrategy<R>>::default())
+    }
+}
+
+/// Adds the resources and transition systems of a rollback state, or only
+/// the systems if it's already initialized in another schedule.
+fn add_roll_state<S: States + FreelyMutableState, M>(
+    app: &mut App,
+    initial: Option<S>,
+    optional: bool,
+    schedule: InternedScheduleLabel,
+    apply_transition: impl IntoScheduleConfigs<ScheduleSystem, M>,
+) -> &mut App {
+    let name = std::any::type_name::<S>();
+
+    if let Some(mut registration) = app
+        .world_mut()
+        .get_resource_mut::<RollStateRegistration<S>>()
+    {
+        if registration.optional != optional {
+            panic!("State {name} is initialized both as an optional and a regular rollback state.");
+        }
+        if registration.schedules.contains(&schedule) {
+            warn!("State {} is already initialized in {:?}.", name, schedule);
+            return app;
         }
+        if registration.initial != initial {
+            warn!(
+                "State {} is already initialized with {:?}, ignoring {:?}.",
+                name, registration.initial, initial
+            );
+        }
+        registration.schedules.push(schedule);
+    } else {
+        check_not_bevy_state::<S>(app.world());
 
-        fn load(stored: &Self::Stored) -> Self::Target {
-            match stored {
-                None => NextState::Unchanged,
-                Some(s) => NextState::Pending(s.to_owned()),
-            }
+        if let Some(initial) = &initial {
+            app.insert_resource(State::new(initial.clone()));
         }
+        if optional {
+            app.init_resource::<RollStateOption<S>>();
+        } else {
+            app.init_resource::<NextState<S>>();
+        }
+
+        app.insert_resource(RollStateRegistration {
+            initial,
+            optional,
+            schedules: vec![schedule],
+            #[cfg(feature = "bevy_ggrs")]
+            ggrs: false,
+        })
+        .init_resource::<InitialStateEntered<S>>()
+        // never updated, so transition messages written by Bevy's
+        // `init_state` or `insert_state` after this are kept until startup
+        .init_resource::<Messages<StateTransitionEvent<S>>>()
+        .add_systems(PreStartup, check_no_bevy_transitions::<S>)
+        .add_observer(reset_roll_state_on_session_reset::<S>);
+    }
+
+    app.add_systems(
+        schedule,
+        (
+            run_enter_schedule::<S>
+                .run_if(resource_equals(InitialStateEntered::<S>(false, default()))),
+            mark_state_initialized::<S>
+                .run_if(resource_equals(InitialStateEntered::<S>(false, default()))),
+            apply_transition,
+        )
+            .chain(),
+    )
+}
+
+/// Panics if Bevy's `init_state` or `insert_state` was used for `S`.
+fn check_not_bevy_state<S: States>(world: &World) {
+    if world.contains_resource::<State<S>>()
+        || world.contains_resource::<Messages<StateTransitionEvent<S>>>()
+    {
+        panic_mixed_registration::<S>();
+    }
+}
+
+/// Panics if Bevy's `init_state` or `insert_state` was used for `S` after it
+/// was initialized as a rollback state, which writes a transition message.
+fn check_no_bevy_transitions<S: States>(messages: Res<Messages<StateTransitionEvent<S>>>) {
+    if !messages.is_empty() {
+        panic_mixed_registration::<S>();
     }
 }
 
-#[derive(Resource, Debug, Reflect, Eq, PartialEq, Clone)]
-#[reflect(Resource)]
+fn panic_mixed_registration<S: States>() -> ! {
+    let name = std::any::type_name::<S>();
+    panic!(
+        "State {name} is initialized both as a rollback state and outside of bevy_roll_safe, \
+         e.g. with Bevy's `init_state`. Rollback states must only be initialized with `RollApp` \
+         methods, such as `init_roll_state`. To also run its transitions in other schedules, \
+         call `init_roll_state_in_schedule` for each of them."
+    );
+}
+
+#[cfg(feature = "bevy_ggrs")]
+fn expect_state_transition_schedule(app: &App) {
+    if app.get_schedule(RollbackStateTransition).is_none() {
+        panic!(
+            "RollbackStateTransition schedule does not exist. \
+             Please add it by adding the `RollbackSchedulePlugin` \
+             or call `init_ggrs_state_in_schedule` with the desired schedule."
+        );
+    }
+}
+
+/// Registers the state resources for rollback and checksumming
+#[cfg(feature = "bevy_ggrs")]
+fn register_ggrs_state<S: States + FreelyMutableState>(app: &mut App) -> &mut App {
+    use crate::strategy::{checksum_next_state, checksum_state, checksum_state_option};
+    use bevy_ggrs::{
+        CloneStrategy, ResourceSnapshotPlugin, RollbackApp, SaveWorld, SaveWorldSystems,
+    };
+
+    let mut registration = app.world_mut().resource_mut::<RollStateRegistration<S>>();
+    if registration.ggrs {
+        // already registered when initialized in another schedule
+        return app;
+    }
+    registration.ggrs = true;
+
+    if registration.optional {
+        // `State<S>` may be absent, so it can't use `checksum_resource`
+        app.add_plugins((
+            ResourceSnapshotPlugin::<StateStrategy<S>>::default(),
+            ResourceSnapshotPlugin::<CloneStrategy<RollStateOption<S>>>::default(),
+        ))
+        .add_systems(
+            SaveWorld,
+            checksum_state_option::<S>.in_set(SaveWorldSystems::Checksum),
+        )
+        .checksum_resource_with_hash::<RollStateOption<S>>();
+    } else {
+        app.add_plugins((
+            ResourceSnapshotPlugin::<StateStrategy<S>>::default(),
+            ResourceSnapshotPlugin::<NextStateStrategy<S>>::default(),
+        ))
+        .checksum_resource::<State<S>>(checksum_state::<S>)
+        .checksum_resource::<NextState<S>>(checksum_next_state::<S>);
+    }
+
+    app.rollback_resource_with_clone::<InitialStateEntered<S>>()
+        .checksum_resource_with_hash::<InitialStateEntered<S>>()
+        .rollback_component_with_clone::<RollDespawnOnExit<S>>()
+        .rollback_component_with_clone::<RollDespawnOnEnter<S>>()
+        .checksum_component_with_hash::<RollDespawnOnExit<S>>()
+        .checksum_component_with_hash::<RollDespawnOnEnter<S>>()
+}
+
+#[derive(Resource, Debug, Reflect, Eq, PartialEq, Hash, Clone)]
+#[reflect(Resource, Hash)]
 pub struct InitialStateEntered<S: States>(bool, PhantomData<S>);
 
 impl<S: States> Default for InitialStateEntered<S> {
@@ -172,9 +511,84 @@ impl<S: States> Default for InitialStateEntered<S> {
     }
 }
 
-fn mark_state_initialized<S: States + FromWorld>(
-    mut state_initialized: ResMut<InitialStateEntered<S>>,
+/// Tracks how a rollback state was initialized.
+#[derive(Resource, Debug)]
+struct RollStateRegistration<S: States> {
+    /// The state to start in, also used by [`reset_roll_state`].
+    initial: Option<S>,
+    /// Whether the state may be absent, see [`RollStateOption`].
+    optional: bool,
+    /// Schedules running the state's transitions.
+    schedules: Vec<InternedScheduleLabel>,
+    /// Whether it's registered for rollback with bevy_ggrs.
+    #[cfg(feature = "bevy_ggrs")]
+    ggrs: bool,
+}
+
+/// Resets the rollback state `S` to its initial value, so
+/// `OnEnter(initial)` is run again in the next rollback frame.
+///
+/// The current state is exited first, running its `OnExit` schedule and
+/// despawning its [`RollDespawnOnExit`] entities, so nothing scoped to it
+/// carries over. [`NextState<S>`] (or [`RollStateOption<S>`]) and
+/// [`RollStateHistory<S>`] are cleared as well.
+///
+/// Can be queued as a command with `commands.queue(reset_roll_state::<S>)`.
+/// Also run for all rollback states when [`RollbackSessionReset`] is
+/// triggered.
+pub fn reset_roll_state<S: States + FreelyMutableState>(world: &mut World) {
+    let Some(registration) = world.get_resource::<RollStateRegistration<S>>() else {
+        let name = std::any::type_name::<S>();
+        warn!("State {} is not a rollback state, not resetting it.", name);
+        return;
+    };
+
+    let initial = registration.initial.clone();
+    let optional = registration.optional;
+
+    if let Some(exited) = world
+        .get_resource::<State<S>>()
+        .map(|state| state.get().clone())
+    {
+        world.try_run_schedule(OnExit(exited.clone())).ok();
+        state_scoped::despawn_on_exit(world, exited);
+    }
+
+    match initial {
+        Some(initial) => world.insert_resource(State::new(initial)),
+        None => {
+            world.remove_resource::<State<S>>();
+        }
+    }
+    if optional {
+        world.insert_resource(RollStateOption::<S>::Unchanged);
+    } else {
+        world.insert_resource(NextState::<S>::Unchanged);
+    }
+    world.insert_resource(InitialStateEntered::<S>::default());
+
+    if let Some(mut history) = world.get_resource_mut::<RollStateHistory<S>>() {
+        history.clear();
+    }
+}
+
+fn reset_roll_state_on_session_reset<S: States + FreelyMutableState>(
+    _reset: On<RollbackSessionReset>,
+    mut commands: Commands,
 ) {
+    commands.queue(reset_roll_state::<S>);
+}
+
+fn insert_state_scoped_resource<R: Resource + FromWorld>(world: &mut World) {
+    let resource = R::from_world(world);
+    world.insert_resource(resource);
+}
+
+fn remove_state_scoped_resource<R: Resource>(world: &mut World) {
+    world.remove_resource::<R>();
+}
+
+fn mark_state_initialized<S: States>(mut state_initialized: ResMut<InitialStateEntered<S>>) {
     state_initialized.0 = true;
 }
 
@@ -183,15 +597,18 @@ pub fn run_enter_schedule<S: States>(world: &mut World) {
     let Some(state) = world.get_resource::<State<S>>() else {
         return;
     };
-    world.try_run_schedule(OnEnter(state.get().clone())).ok();
+    let state = state.get().clone();
+    state_history::record_transition(world, None, Some(state.clone()));
+    world.try_run_schedule(OnEnter(state.clone())).ok();
+    state_scoped::despawn_on_enter(world, state);
 }
 
 /// If a new state is queued in [`NextState<S>`], this system:
 /// - Takes the new state value from [`NextState<S>`] and updates [`State<S>`].
 /// - Sends a relevant [`StateTransitionEvent`]
-/// - Runs the [`OnExit(exited_state)`] schedule, if it exists.
+/// - Runs the [`OnExit`]`(exited_state)` schedule, if it exists.
 /// - Runs the [`OnTransition { from: exited_state, to: entered_state }`](OnTransition), if it exists.
-/// - Runs the [`OnEnter(entered_state)`] schedule, if it exists.
+/// - Runs the [`OnEnter`]`(entered_state)` schedule, if it exists.
 pub fn apply_state_transition<S: States + FreelyMutableState>(world: &mut World) {
     // We want to take the `NextState` resource,
     // but only mark it as changed if it wasn't empty.
@@ -206,25 +623,338 @@ pub fn apply_state_transition<S: States + FreelyMutableState>(world: &mut World)
                 if *state_resource != entered {
                     let exited = state_resource.get().clone();
                     *state_resource = State::new(entered.clone());
+                    state_history::record_transition(
+                        world,
+                        Some(exited.clone()),
+                        Some(entered.clone()),
+                    );
                     // world.send_event(StateTransitionEvent {
                     //     exited: Some(exited.clone()),
                     //     entered: Some(entered.clone()),
                     // });
                     // Try to run the schedules if they exist.
                     world.try_run_schedule(OnExit(exited.clone())).ok();
+                    state_scoped::despawn_on_exit(world, exited.clone());
                     world
                         .try_run_schedule(OnTransition {
                             exited,
                             entered: entered.clone(),
                         })
                         .ok();
-                    world.try_run_schedule(OnEnter(entered)).ok();
+                    world.try_run_schedule(OnEnter(entered.clone())).ok();
+                    state_scoped::despawn_on_enter(world, entered);
                 }
             }
             None => {
                 world.insert_resource(State::new(entered.clone()));
-                world.try_run_schedule(OnEnter(entered)).ok();
+                state_history::record_transition(world, None, Some(entered.clone()));
+                world.try_run_schedule(OnEnter(entered.clone())).ok();
+                state_scoped::despawn_on_enter(world, entered);
             }
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(States, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    enum LobbyState {
+        Waiting,
+        Playing,
+    }
+
+    #[derive(Resource, Default)]
+    struct Entered(Vec<LobbyState>);
+
+    #[test]
+    fn insert_roll_state_enters_initial_state() {
+        let mut app = App::new();
+        app.add_plugins(RollbackSchedulePlugin::new(Update))
+            .init_resource::<Entered>()
+            .insert_roll_state(LobbyState::Playing)
+            .add_systems(
+                OnEnter(LobbyState::Playing),
+                |mut entered: ResMut<Entered>| entered.0.push(LobbyState::Playing),
+            )
+            .add_systems(
+                OnEnter(LobbyState::Waiting),
+                |mut entered: ResMut<Entered>| entered.0.push(LobbyState::Waiting),
+            );
+
+        app.update();
+        app.update();
+        assert_eq!(
+            app.world().resource::<Entered>().0,
+            vec![LobbyState::Playing]
+        );
+
+        // as after rolling back to the first frame
+        *app.world_mut()
+            .resource_mut::<InitialStateEntered<LobbyState>>() = default();
+        app.update();
+        assert_eq!(
+            app.world().resource::<Entered>().0,
+            vec![LobbyState::Playing, LobbyState::Playing]
+        );
+    }
+
+    #[test]
+    fn session_reset_reenters_initial_state() {
+        let mut app = App::new();
+        app.add_plugins(RollbackSchedulePlugin::new(Update).with_frame_count())
+            .init_resource::<Entered>()
+            .insert_roll_state(LobbyState::Waiting)
+            .add_systems(
+                OnEnter(LobbyState::Waiting),
+                |mut entered: ResMut<Entered>| entered.0.push(LobbyState::Waiting),
+            );
+
+        app.update();
+        app.world_mut()
+            .resource_mut::<NextState<LobbyState>>()
+            .set(LobbyState::Playing);
+        app.update();
+        assert_eq!(
+            *app.world().resource::<State<LobbyState>>().get(),
+            LobbyState::Playing
+        );
+
+        app.world_mut().trigger(RollbackSessionReset);
+        app.world_mut().flush();
+        assert_eq!(app.world().resource::<RollFrameCount>().0, 0);
+        assert_eq!(
+            *app.world().resource::<State<LobbyState>>().get(),
+            LobbyState::Waiting
+        );
+
+        app.update();
+        assert_eq!(
+            app.world().resource::<Entered>().0,
+            vec![LobbyState::Waiting, LobbyState::Waiting]
+        );
+    }
+
+    #[test]
+    fn session_reset_resets_state_history() {
+        let mut app = App::new();
+        app.add_plugins(RollbackSchedulePlugin::new(Update).with_frame_count())
+            .insert_roll_state(LobbyState::Waiting)
+            .init_resource::<RollStateHistory<LobbyState>>();
+
+        let play = |app: &mut App| {
+            app.world_mut()
+                .resource_mut::<NextState<LobbyState>>()
+                .set(LobbyState::Playing);
+            app.update();
+        };
+
+        for _ in 0..3 {
+            app.update();
+        }
+        play(&mut app);
+
+        app.world_mut().trigger(RollbackSessionReset);
+        app.world_mut().flush();
+        app.update();
+        play(&mut app);
+
+        let records: Vec<_> = app
+            .world()
+            .resource::<RollStateHistory<LobbyState>>()
+            .records()
+            .map(|record| (record.frame, record.was_resimulation))
+            .collect();
+        assert_eq!(records, vec![(Some(1), false), (Some(2), false)]);
+    }
+
+    #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+    struct Menu;
+
+    #[test]
+    fn transitions_in_second_schedule() {
+        let mut app = App::new();
+        app.add_plugins(RollbackSchedulePlugin::new(Update))
+            .init_resource::<Entered>()
+            .insert_roll_state(LobbyState::Waiting)
+            .insert_roll_state_in_schedule(LobbyState::Waiting, Menu)
+            .add_systems(
+                OnEnter(LobbyState::Playing),
+                |mut entered: ResMut<Entered>| entered.0.push(LobbyState::Playing),
+            );
+
+        app.world_mut()
+            .resource_mut::<NextState<LobbyState>>()
+            .set(LobbyState::Playing);
+        app.world_mut().run_schedule(Menu);
+        assert_eq!(
+            app.world().resource::<Entered>().0,
+            vec![LobbyState::Playing]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "needs room")]
+    fn empty_state_history_panics() {
+        RollStateHistory::<LobbyState>::new(0);
+    }
+
+    #[test]
+    fn state_history_in_two_rollback_schedules() {
+        let mut app = App::new();
+        app.add_plugins(RollbackSchedulePlugin::new(Update).with_frame_count())
+            .insert_roll_state(LobbyState::Waiting)
+            .insert_roll_state_in_schedule(LobbyState::Waiting, RollbackPostUpdate)
+            .init_resource::<RollStateHistory<LobbyState>>()
+            .add_systems(RollbackUpdate, |mut next: ResMut<NextState<LobbyState>>| {
+                next.set(LobbyState::Waiting)
+            });
+
+        app.update();
+        app.world_mut()
+            .resource_mut::<NextState<LobbyState>>()
+            .set(LobbyState::Playing);
+        app.update();
+
+        let records: Vec<_> = app
+            .world()
+            .resource::<RollStateHistory<LobbyState>>()
+            .records()
+            .map(|record| (record.frame, record.entered, record.was_resimulation))
+            .collect();
+        assert_eq!(
+            records,
+            vec![
+                (Some(1), Some(LobbyState::Waiting), false),
+                (Some(2), Some(LobbyState::Playing), false),
+                (Some(2), Some(LobbyState::Waiting), false),
+            ]
+        );
+    }
+
+    #[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    enum MenuState {
+        #[default]
+        Main,
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of bevy_roll_safe")]
+    fn mixed_registration_panics() {
+        let mut app = App::new();
+        app.add_plugins((
+            bevy::state::app::StatesPlugin,
+            RollbackSchedulePlugin::new(Update),
+        ))
+        .init_state::<MenuState>()
+        .init_roll_state::<MenuState>();
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of bevy_roll_safe")]
+    fn mixed_registration_in_reverse_order_panics() {
+        let mut app = App::new();
+        app.add_plugins((
+            bevy::state::app::StatesPlugin,
+            RollbackSchedulePlugin::new(Update),
+        ))
+        .init_roll_state::<MenuState>()
+        .insert_state(MenuState::Main);
+
+        app.update();
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of bevy_roll_safe")]
+    fn mixed_optional_registration_panics() {
+        let mut app = App::new();
+        app.add_plugins((
+            bevy::state::app::StatesPlugin,
+            RollbackSchedulePlugin::new(Update),
+        ))
+        .insert_roll_state_option::<MenuState>(None)
+        .insert_state(MenuState::Main);
+
+        app.update();
+    }
+
+    #[derive(Resource, Default, Clone)]
+    struct RoundScore(u32);
+
+    #[test]
+    fn state_scoped_resource() {
+        let mut app = App::new();
+        app.add_plugins(RollbackSchedulePlugin::new(Update))
+            .insert_roll_state(LobbyState::Waiting)
+            .add_roll_state_scoped_resource::<_, RoundScore>(LobbyState::Playing);
+
+        app.update();
+        assert!(!app.world().contains_resource::<RoundScore>());
+
+        app.world_mut()
+            .resource_mut::<NextState<LobbyState>>()
+            .set(LobbyState::Playing);
+        app.update();
+        app.world_mut().resource_mut::<RoundScore>().0 += 1;
+
+        app.world_mut()
+            .resource_mut::<NextState<LobbyState>>()
+            .set(LobbyState::Waiting);
+        app.update();
+        assert!(!app.world().contains_resource::<RoundScore>());
+
+        // a fresh resource on the next round
+        app.world_mut()
+            .resource_mut::<NextState<LobbyState>>()
+            .set(LobbyState::Playing);
+        app.update();
+        assert_eq!(app.world().resource::<RoundScore>().0, 0);
+    }
+
+    #[test]
+    fn session_reset_removes_state_scoped_resource() {
+        let mut app = App::new();
+        app.add_plugins(RollbackSchedulePlugin::new(Update))
+            .insert_roll_state(LobbyState::Waiting)
+            .add_roll_state_scoped_resource::<_, RoundScore>(LobbyState::Playing);
+
+        app.update();
+        app.world_mut()
+            .resource_mut::<NextState<LobbyState>>()
+            .set(LobbyState::Playing);
+        app.update();
+        assert!(app.world().contains_resource::<RoundScore>());
+
+        app.world_mut().trigger(RollbackSessionReset);
+        app.world_mut().flush();
+        assert!(!app.world().contains_resource::<RoundScore>());
+    }
+
+    #[cfg(feature = "bevy_ggrs")]
+    #[test]
+    fn state_scoped_resource_is_rolled_back() {
+        use crate::test_utils::ggrs_test_app;
+        use bevy_ggrs::{AdvanceWorld, LoadWorld, RollbackFrameCount, SaveWorld};
+
+        let mut app = ggrs_test_app();
+        app.insert_ggrs_state(LobbyState::Playing)
+            .add_ggrs_state_scoped_resource::<_, RoundScore>(LobbyState::Playing);
+
+        app.world_mut().run_schedule(AdvanceWorld);
+        app.world_mut().resource_mut::<RoundScore>().0 = 3;
+        app.world_mut().run_schedule(SaveWorld);
+
+        app.world_mut()
+            .resource_mut::<NextState<LobbyState>>()
+            .set(LobbyState::Waiting);
+        app.world_mut().resource_mut::<RollbackFrameCount>().0 = 1;
+        app.world_mut().run_schedule(AdvanceWorld);
+        assert!(!app.world().contains_resource::<RoundScore>());
+
+        // roll back across `OnExit(Playing)`
+        app.world_mut().resource_mut::<RollbackFrameCount>().0 = 0;
+        app.world_mut().run_schedule(LoadWorld);
+        assert_eq!(app.world().resource::<RoundScore>().0, 3);
+    }
+}
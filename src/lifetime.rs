@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+
+use crate::{RollCommandsSystems, RollQuery, RollbackPostUpdate};
+
+/// Despawns the entity after the given number of rollback frames.
+///
+/// The remaining lifetime is counted down at the end of each rollback frame,
+/// in [`RollbackPostUpdate`], and the entity is despawned when it reaches
+/// zero, i.e. an entity spawned with `RollLifetime(1)` lives for the rest of
+/// the frame it was spawned in.
+///
+/// A [`RollLifetimeExpired`] event is triggered right before despawning.
+///
+/// Requires the [`RollLifetimePlugin`].
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[reflect(Component, Hash)]
+pub struct RollLifetime(pub u32);
+
+/// Triggered when a [`RollLifetime`] runs out, before the entity is despawned.
+///
+/// Entities are processed in [`RollQuery`] order, so observers run in the same
+/// order after rollbacks. Prefer global observers, as observers attached to
+/// specific entities are not restored when rollback entities are respawned.
+#[derive(EntityEvent, Clone, Copy, Debug)]
+pub struct RollLifetimeExpired {
+    pub entity: Entity,
+}
+
+/// System set containing the system that counts down [`RollLifetime`]s.
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RollLifetimeSystems;
+
+/// Counts down [`RollLifetime`]s and despawns expired entities.
+///
+/// Depends on the [`RollbackSchedulePlugin`](crate::RollbackSchedulePlugin).
+/// With the `bevy_ggrs` feature, [`RollLifetime`] is also registered for
+/// rollback and checksumming.
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_roll_safe::prelude::*;
+/// # use bevy_roll_safe::{RollLifetime, RollLifetimeExpired, RollLifetimePlugin};
+/// fn fire(mut commands: Commands) {
+///     // a hitbox that lasts for 5 frames
+///     commands.spawn((Transform::default(), RollLifetime(5)));
+/// }
+///
+/// fn on_expired(expired: On<RollLifetimeExpired>) {
+///     info!("{} expired", expired.entity);
+/// }
+///
+/// # fn start() {
+/// # let mut app = App::new();
+/// app.add_plugins((RollbackSchedulePlugin::new(FixedUpdate), RollLifetimePlugin))
+///     .add_systems(RollbackUpdate, fire)
+///     .add_observer(on_expired);
+/// # }
+/// ```
+pub struct RollLifetimePlugin;
+
+impl Plugin for RollLifetimePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<RollLifetime>().add_systems(
+            RollbackPostUpdate,
+            tick_lifetimes
+                .in_set(RollLifetimeSystems)
+                .in_set(RollCommandsSystems),
+        );
+
+        #[cfg(feature = "bevy_ggrs")]
+        {
+            use bevy_ggrs::RollbackApp;
+            app.rollback_component_with_copy::<RollLifetime>()
+                .checksum_component_with_hash::<RollLifetime>();
+        }
+    }
+}
+
+/// Counts down [`RollLifetime`]s, despawning entities whose lifetime ran out.
+pub fn tick_lifetimes(
+    mut commands: Commands,
+    mut lifetimes: RollQuery<(Entity, &mut RollLifetime)>,
+) {
+    for (entity, mut lifetime) in lifetimes.iter_mut() {
+        lifetime.0 = lifetime.0.saturating_sub(1);
+        if lifetime.0 == 0 {
+            commands.trigger(RollLifetimeExpired { entity });
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::TestRollbackSchedule, RollbackSchedulePlugin};
+
+    #[derive(Resource, Default)]
+    struct Expired(Vec<Entity>);
+
+    #[test]
+    fn despawns_after_lifetime() {
+        let mut app = App::new();
+        app.add_plugins((
+            RollbackSchedulePlugin::new(TestRollbackSchedule),
+            RollLifetimePlugin,
+        ))
+        .init_resource::<Expired>()
+        .add_observer(
+            |expired: On<RollLifetimeExpired>, mut expired_entities: ResMut<Expired>| {
+                expired_entities.0.push(expired.entity);
+            },
+        );
+
+        let short = app.world_mut().spawn(RollLifetime(1)).id();
+        let long = app.world_mut().spawn(RollLifetime(2)).id();
+
+        app.world_mut().run_schedule(TestRollbackSchedule);
+        assert!(app.world().get_entity(short).is_err());
+        assert_eq!(
+            app.world().get::<RollLifetime>(long),
+            Some(&RollLifetime(1))
+        );
+
+        app.world_mut().run_schedule(TestRollbackSchedule);
+        assert!(app.world().get_entity(long).is_err());
+        assert_eq!(app.world().resource::<Expired>().0, vec![short, long]);
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    any::Any,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use bevy::{
+    ecs::{
+        component::Tick,
+        query::FilteredAccessSet,
+        system::{SystemMeta, SystemParam},
+        world::unsafe_world_cell::UnsafeWorldCell,
+    },
+    prelude::*,
+};
+
+use crate::RollbackSessionReset;
+
+/// Rollback-safe replacement for Bevy's [`Local<T>`].
+///
+/// Values are stored outside the system in the [`RollLocals`] resource, which
+/// is snapshotted by `bevy_ggrs` when the
+/// [`RollbackSchedulePlugin`](crate::RollbackSchedulePlugin) is added, so
+/// counters, cooldowns and caches are rolled back along with the rest of the
+/// world.
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_roll_safe::prelude::*;
+/// fn count_frames(mut frames: RollLocal<u32>) {
+///     *frames += 1;
+///     info!("frames simulated: {}", *frames);
+/// }
+///
+/// # fn start() {
+/// # let mut app = App::new();
+/// app.add_plugins(RollbackSchedulePlugin::new(FixedUpdate))
+///     .add_systems(RollbackUpdate, count_frames);
+/// # }
+/// ```
+///
+/// Unlike with [`Local<T>`], `T` needs to implement [`Clone`] so it can be
+/// snapshotted.
+///
+/// All values live in the same resource, so a `RollLocal` in a system outside
+/// the rollback schedules is rewound on load as well. Use [`Local<T>`] there
+/// instead. [`RollLocals`] isn't included in the checksum either, so a desync
+/// in a `RollLocal` only shows up once it affects checksummed state.
+pub struct RollLocal<'s, T: RollLocalValue> {
+    value: MutexGuard<'s, Box<dyn RollLocalValue>>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: RollLocalValue> Deref for RollLocal<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        let value: &dyn Any = &**self.value;
+        value
+            .downcast_ref()
+            .expect("RollLocal slot should contain a value of the requested type")
+    }
+}
+
+impl<T: RollLocalValue> DerefMut for RollLocal<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        let value: &mut dyn Any = &mut **self.value;
+        value
+            .downcast_mut()
+            .expect("RollLocal slot should contain a value of the requested type")
+    }
+}
+
+/// Values that can be stored in a [`RollLocal`].
+///
+/// Implemented for all types that are [`Clone`], [`Send`] and [`Sync`].
+pub trait RollLocalValue: Any + Send + Sync {
+    fn clone_value(&self) -> Box<dyn RollLocalValue>;
+}
+
+impl<T: Any + Send + Sync + Clone> RollLocalValue for T {
+    fn clone_value(&self) -> Box<dyn RollLocalValue> {
+        Box::new(self.clone())
+    }
+}
+
+type RollLocalSlot = Arc<Mutex<Box<dyn RollLocalValue>>>;
+
+/// Storage for the values of all [`RollLocal`] system params.
+///
+/// Each system param holds on to its own slot, so systems using [`RollLocal`]
+/// don't conflict with each other. Only snapshotting reads all slots.
+#[derive(Resource, Default)]
+pub struct RollLocals {
+    slots: Vec<RollLocalEntry>,
+}
+
+struct RollLocalEntry {
+    slot: RollLocalSlot,
+    /// Value the slot was created with, restored when loading a snapshot taken
+    /// before the slot existed, and when a new session starts.
+    initial: Box<dyn RollLocalValue>,
+}
+
+/// Puts all [`RollLocal`] values back to their initial value.
+pub(crate) fn reset_roll_locals(_reset: On<RollbackSessionReset>, locals: Res<RollLocals>) {
+    for entry in &locals.slots {
+        *lock(&entry.slot) = entry.initial.clone_value();
+    }
+}
+
+fn lock(slot: &RollLocalSlot) -> MutexGuard<'_, Box<dyn RollLocalValue>> {
+    slot.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[doc(hidden)]
+pub struct RollLocalState<T> {
+    slot: RollLocalSlot,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+// SAFETY: only accesses the slot owned by this system param, no world access
+// is registered or performed.
+unsafe impl<'a, T: RollLocalValue + FromWorld> SystemParam for RollLocal<'a, T> {
+    type State = RollLocalState<T>;
+    type Item<'w, 's> = RollLocal<'s, T>;
+
+    fn init_state(world: &mut World) -> Self::State {
+        let value: Box<dyn RollLocalValue> = Box::new(T::from_world(world));
+        let initial = value.clone_value();
+        let slot = Arc::new(Mutex::new(value));
+        world
+            .get_resource_or_init::<RollLocals>()
+            .slots
+            .push(RollLocalEntry {
+                slot: slot.clone(),
+                initial,
+            });
+        RollLocalState {
+            slot,
+            _phantom: default(),
+        }
+    }
+
+    fn init_access(
+        _state: &Self::State,
+        _system_meta: &mut SystemMeta,
+        _component_access_set: &mut FilteredAccessSet,
+        _world: &mut World,
+    ) {
+    }
+
+    #[inline]
+    unsafe fn get_param<'w, 's>(
+        state: &'s mut Self::State,
+        _system_meta: &SystemMeta,
+        _world: UnsafeWorldCell<'w>,
+        _change_tick: Tick,
+    ) -> Self::Item<'w, 's> {
+        RollLocal {
+            value: lock(&state.slot),
+            _phantom: default(),
+        }
+    }
+}
+
+#[cfg(feature = "bevy_ggrs")]
+pub(crate) struct RollLocalsStrategy;
+
+#[cfg(feature = "bevy_ggrs")]
+impl bevy_ggrs::Strategy for RollLocalsStrategy {
+    type Target = RollLocals;
+    type Stored = Vec<Box<dyn RollLocalValue>>;
+
+    fn store(target: &Self::Target) -> Self::Stored {
+        target
+            .slots
+            .iter()
+            .map(|entry| lock(&entry.slot).clone_value())
+            .collect()
+    }
+
+    fn load(stored: &Self::Stored) -> Self::Target {
+        RollLocals {
+            slots: stored
+                .iter()
+                .map(|value| RollLocalEntry {
+                    slot: Arc::new(Mutex::new((**value).clone_value())),
+                    initial: (**value).clone_value(),
+                })
+                .collect(),
+        }
+    }
+
+    fn update(target: &mut Self::Target, stored: &Self::Stored) {
+        // write into the existing slots, since systems hold on to them.
+        // slots created after the snapshot was taken didn't exist yet at that
+        // frame, so they go back to their initial value.
+        for (index, entry) in target.slots.iter().enumerate() {
+            let value = stored.get(index).unwrap_or(&entry.initial);
+            *lock(&entry.slot) = (**value).clone_value();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Resource, Default)]
+    struct Counts(Vec<u32>);
+
+    fn count(mut counter: RollLocal<u32>, mut counts: ResMut<Counts>) {
+        *counter += 1;
+        counts.0.push(*counter);
+    }
+
+    #[test]
+    fn roll_locals_are_per_system() {
+        let mut app = App::new();
+        app.init_resource::<Counts>()
+            .add_systems(Update, (count, count).chain());
+
+        app.update();
+        app.update();
+
+        assert_eq!(app.world().resource::<Counts>().0, vec![1, 1, 2, 2]);
+        assert_eq!(app.world().resource::<RollLocals>().slots.len(), 2);
+    }
+
+    #[test]
+    fn session_reset_resets_roll_locals() {
+        use crate::{RollbackSchedulePlugin, RollbackUpdate};
+
+        let mut app = App::new();
+        app.add_plugins(RollbackSchedulePlugin::new(FixedUpdate))
+            .init_resource::<Counts>()
+            .add_systems(RollbackUpdate, count);
+
+        app.world_mut().run_schedule(FixedUpdate);
+        app.world_mut().run_schedule(FixedUpdate);
+        app.world_mut().trigger(RollbackSessionReset);
+        app.world_mut().run_schedule(FixedUpdate);
+
+        assert_eq!(app.world().resource::<Counts>().0, vec![1, 2, 1]);
+    }
+
+    #[test]
+    #[cfg(feature = "bevy_ggrs")]
+    fn can_roll_back_roll_locals() {
+        use crate::{test_utils::ggrs_test_app, RollbackUpdate};
+        use bevy_ggrs::{AdvanceWorld, LoadWorld, SaveWorld};
+
+        let mut app = ggrs_test_app();
+        app.init_resource::<Counts>()
+            .add_systems(RollbackUpdate, count);
+
+        app.world_mut().run_schedule(AdvanceWorld);
+        app.world_mut().run_schedule(SaveWorld);
+        app.world_mut().run_schedule(AdvanceWorld);
+        app.world_mut().run_schedule(AdvanceWorld);
+        assert_eq!(app.world().resource::<Counts>().0, vec![1, 2, 3]);
+
+        app.world_mut().run_schedule(LoadWorld);
+        app.world_mut().run_schedule(AdvanceWorld);
+        assert_eq!(app.world().resource::<Counts>().0, vec![1, 2, 3, 2]);
+    }
+
+    #[test]
+    #[cfg(feature = "bevy_ggrs")]
+    fn rolls_back_roll_locals_to_before_first_run() {
+        use crate::{test_utils::ggrs_test_app, RollbackUpdate};
+        use bevy_ggrs::{AdvanceWorld, LoadWorld, SaveWorld};
+
+        let mut app = ggrs_test_app();
+        app.init_resource::<Counts>()
+            .add_systems(RollbackUpdate, count);
+
+        // bevy_ggrs saves frame 0 before the first advance
+        app.world_mut().run_schedule(SaveWorld);
+        app.world_mut().run_schedule(AdvanceWorld);
+        app.world_mut().run_schedule(AdvanceWorld);
+        assert_eq!(app.world().resource::<Counts>().0, vec![1, 2]);
+
+        app.world_mut().run_schedule(LoadWorld);
+        app.world_mut().run_schedule(AdvanceWorld);
+        assert_eq!(app.world().resource::<Counts>().0, vec![1, 2, 1]);
+    }
+}
@@ -0,0 +1,425 @@
+//! Deterministic math types
+//!
+//! The `math_determinism` feature makes Bevy use `libm` for transcendental
+//! functions, but glam's SIMD implementations (used for [`Quat`] and
+//! [`Vec3A`]) may still order or combine operations
+//! differently on different architectures.
+//!
+//! The types in this module are thin wrappers around glam types that only use
+//! basic IEEE 754 operations (which are correctly rounded on all platforms) in
+//! a fixed order, and `libm` for everything else. Results are bit-identical on
+//! x86_64 and aarch64. Rust never fuses multiplies and adds on its own, so no
+//! fused multiply-add is used unless explicitly asked for.
+//!
+//! The wrappers intentionally don't implement `Deref`, so glam's own methods
+//! can't be called by accident. Convert with [`From`]/[`Into`] when the
+//! simulation is done, e.g. for rendering.
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use bevy::prelude::*;
+use bevy_math::ops;
+
+/// Deterministic 2D vector
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub struct RollVec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl RollVec2 {
+    pub const ZERO: Self = Self::new(0.0, 0.0);
+    pub const X: Self = Self::new(1.0, 0.0);
+    pub const Y: Self = Self::new(0.0, 1.0);
+
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn dot(self, rhs: Self) -> f32 {
+        self.x * rhs.x + self.y * rhs.y
+    }
+
+    /// The z component of the 3D cross product
+    pub fn perp_dot(self, rhs: Self) -> f32 {
+        self.x * rhs.y - self.y * rhs.x
+    }
+
+    pub fn length_squared(self) -> f32 {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn distance(self, rhs: Self) -> f32 {
+        (self - rhs).length()
+    }
+
+    /// Returns a vector with length 1, or zero if the length is zero or not
+    /// finite.
+    pub fn normalize_or_zero(self) -> Self {
+        let length = self.length();
+        if length > 0.0 && length.is_finite() {
+            self * (1.0 / length)
+        } else {
+            Self::ZERO
+        }
+    }
+
+    pub fn lerp(self, rhs: Self, s: f32) -> Self {
+        self + (rhs - self) * s
+    }
+
+    /// Rotates the vector counter-clockwise by `angle` radians
+    pub fn rotate(self, angle: f32) -> Self {
+        let (sin, cos) = ops::sin_cos(angle);
+        Self::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    /// Angle of the vector in radians, measured counter-clockwise from `X`
+    pub fn to_angle(self) -> f32 {
+        ops::atan2(self.y, self.x)
+    }
+}
+
+/// Deterministic 3D vector
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub struct RollVec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl RollVec3 {
+    pub const ZERO: Self = Self::new(0.0, 0.0, 0.0);
+    pub const X: Self = Self::new(1.0, 0.0, 0.0);
+    pub const Y: Self = Self::new(0.0, 1.0, 0.0);
+    pub const Z: Self = Self::new(0.0, 0.0, 1.0);
+
+    pub const fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn dot(self, rhs: Self) -> f32 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    pub fn cross(self, rhs: Self) -> Self {
+        Self::new(
+            self.y * rhs.z - self.z * rhs.y,
+            self.z * rhs.x - self.x * rhs.z,
+            self.x * rhs.y - self.y * rhs.x,
+        )
+    }
+
+    pub fn length_squared(self) -> f32 {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn distance(self, rhs: Self) -> f32 {
+        (self - rhs).length()
+    }
+
+    /// Returns a vector with length 1, or zero if the length is zero or not
+    /// finite.
+    pub fn normalize_or_zero(self) -> Self {
+        let length = self.length();
+        if length > 0.0 && length.is_finite() {
+            self * (1.0 / length)
+        } else {
+            Self::ZERO
+        }
+    }
+
+    pub fn lerp(self, rhs: Self, s: f32) -> Self {
+        self + (rhs - self) * s
+    }
+}
+
+/// Deterministic rotation quaternion
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct RollQuat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Default for RollQuat {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl RollQuat {
+    pub const IDENTITY: Self = Self::from_xyzw(0.0, 0.0, 0.0, 1.0);
+
+    pub const fn from_xyzw(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Self { x, y, z, w }
+    }
+
+    /// Rotation of `angle` radians around `axis`, which must be normalized.
+    pub fn from_axis_angle(axis: RollVec3, angle: f32) -> Self {
+        let (sin, cos) = ops::sin_cos(angle * 0.5);
+        let v = axis * sin;
+        Self::from_xyzw(v.x, v.y, v.z, cos)
+    }
+
+    pub fn from_rotation_x(angle: f32) -> Self {
+        Self::from_axis_angle(RollVec3::X, angle)
+    }
+
+    pub fn from_rotation_y(angle: f32) -> Self {
+        Self::from_axis_angle(RollVec3::Y, angle)
+    }
+
+    pub fn from_rotation_z(angle: f32) -> Self {
+        Self::from_axis_angle(RollVec3::Z, angle)
+    }
+
+    pub fn dot(self, rhs: Self) -> f32 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+    }
+
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    /// Returns the quaternion scaled to length 1.
+    ///
+    /// Uses a full division by the length, instead of the reciprocal square
+    /// root estimates some SIMD implementations use.
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        Self::from_xyzw(
+            self.x / length,
+            self.y / length,
+            self.z / length,
+            self.w / length,
+        )
+    }
+
+    /// The inverse of a normalized quaternion
+    pub fn inverse(self) -> Self {
+        Self::from_xyzw(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn mul_quat(self, rhs: Self) -> Self {
+        let (x0, y0, z0, w0) = (self.x, self.y, self.z, self.w);
+        let (x1, y1, z1, w1) = (rhs.x, rhs.y, rhs.z, rhs.w);
+        Self::from_xyzw(
+            w0 * x1 + x0 * w1 + y0 * z1 - z0 * y1,
+            w0 * y1 - x0 * z1 + y0 * w1 + z0 * x1,
+            w0 * z1 + x0 * y1 - y0 * x1 + z0 * w1,
+            w0 * w1 - x0 * x1 - y0 * y1 - z0 * z1,
+        )
+    }
+
+    /// Rotates a vector by this (normalized) quaternion.
+    pub fn mul_vec3(self, rhs: RollVec3) -> RollVec3 {
+        let axis = RollVec3::new(self.x, self.y, self.z);
+        let t = axis.cross(rhs) * 2.0;
+        rhs + t * self.w + axis.cross(t)
+    }
+
+    /// Normalized linear interpolation, takes the shortest path.
+    pub fn nlerp(self, rhs: Self, s: f32) -> Self {
+        let rhs = if self.dot(rhs) < 0.0 { -rhs } else { rhs };
+        Self::from_xyzw(
+            self.x + (rhs.x - self.x) * s,
+            self.y + (rhs.y - self.y) * s,
+            self.z + (rhs.z - self.z) * s,
+            self.w + (rhs.w - self.w) * s,
+        )
+        .normalize()
+    }
+}
+
+impl Neg for RollQuat {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::from_xyzw(-self.x, -self.y, -self.z, -self.w)
+    }
+}
+
+impl Mul for RollQuat {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        self.mul_quat(rhs)
+    }
+}
+
+impl Mul<RollVec3> for RollQuat {
+    type Output = RollVec3;
+
+    fn mul(self, rhs: RollVec3) -> RollVec3 {
+        self.mul_vec3(rhs)
+    }
+}
+
+macro_rules! impl_vec_ops {
+    ($vec:ident, $($field:ident),+) => {
+        impl Add for $vec {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self { $($field: self.$field + rhs.$field),+ }
+            }
+        }
+
+        impl AddAssign for $vec {
+            fn add_assign(&mut self, rhs: Self) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl Sub for $vec {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self { $($field: self.$field - rhs.$field),+ }
+            }
+        }
+
+        impl SubAssign for $vec {
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl Mul<f32> for $vec {
+            type Output = Self;
+
+            fn mul(self, rhs: f32) -> Self {
+                Self { $($field: self.$field * rhs),+ }
+            }
+        }
+
+        impl Neg for $vec {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self { $($field: -self.$field),+ }
+            }
+        }
+    };
+}
+
+impl_vec_ops!(RollVec2, x, y);
+impl_vec_ops!(RollVec3, x, y, z);
+
+impl From<Vec2> for RollVec2 {
+    fn from(v: Vec2) -> Self {
+        Self::new(v.x, v.y)
+    }
+}
+
+impl From<RollVec2> for Vec2 {
+    fn from(v: RollVec2) -> Self {
+        Vec2::new(v.x, v.y)
+    }
+}
+
+impl From<Vec3> for RollVec3 {
+    fn from(v: Vec3) -> Self {
+        Self::new(v.x, v.y, v.z)
+    }
+}
+
+impl From<RollVec3> for Vec3 {
+    fn from(v: RollVec3) -> Self {
+        Vec3::new(v.x, v.y, v.z)
+    }
+}
+
+impl From<Quat> for RollQuat {
+    fn from(q: Quat) -> Self {
+        Self::from_xyzw(q.x, q.y, q.z, q.w)
+    }
+}
+
+impl From<RollQuat> for Quat {
+    fn from(q: RollQuat) -> Self {
+        Quat::from_xyzw(q.x, q.y, q.z, q.w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vectors: bit patterns of the expected results, which must be the
+    // same on all supported architectures.
+
+    fn bits2(v: RollVec2) -> [u32; 2] {
+        [v.x.to_bits(), v.y.to_bits()]
+    }
+
+    fn bits3(v: RollVec3) -> [u32; 3] {
+        [v.x.to_bits(), v.y.to_bits(), v.z.to_bits()]
+    }
+
+    fn bits4(q: RollQuat) -> [u32; 4] {
+        [q.x.to_bits(), q.y.to_bits(), q.z.to_bits(), q.w.to_bits()]
+    }
+
+    #[test]
+    fn vec2_test_vectors() {
+        let a = RollVec2::new(0.1, 0.7);
+        let b = RollVec2::new(-3.3, 2.9);
+
+        assert_eq!(a.dot(b).to_bits(), 0x3FD9_9999);
+        assert_eq!(a.length().to_bits(), 0x3F35_04F3);
+        assert_eq!(bits2(b.normalize_or_zero()), [0xBF40_4C58, 0x3F28_FD46]);
+        assert_eq!(bits2(a.rotate(1.234)), [0xBF20_AC1C, 0x3EA6_C320]);
+        assert_eq!(b.to_angle().to_bits(), 0x401A_EB76);
+    }
+
+    #[test]
+    fn vec3_test_vectors() {
+        let a = RollVec3::new(0.1, 0.7, -1.9);
+        let b = RollVec3::new(-3.3, 2.9, 0.01);
+
+        assert_eq!(bits3(a.cross(b)), [0x40B0_8B44, 0x40C8_9BA6, 0x4026_6666]);
+        assert_eq!(
+            bits3(a.normalize_or_zero()),
+            [0x3D4A_0A71, 0x3EB0_C923, 0xBF6F_EC66]
+        );
+        assert_eq!(
+            bits3(a.lerp(b, 0.3)),
+            [0xBF6B_851E, 0x3FAE_147B, 0xBFA9_DB22]
+        );
+    }
+
+    #[test]
+    fn quat_test_vectors() {
+        let axis = RollVec3::new(1.0, 2.0, 3.0).normalize_or_zero();
+        let q = RollQuat::from_axis_angle(axis, 0.77);
+        let r = RollQuat::from_rotation_y(-2.1);
+
+        assert_eq!(
+            bits4(q),
+            [0x3DCD_900B, 0x3E4D_900B, 0x3E9A_2C08, 0x3F6D_42AA]
+        );
+        assert_eq!(
+            bits4(q * r),
+            [0x3E9F_4D89, 0xBF34_3C13, 0x3D80_8949, 0x3F22_A1A2]
+        );
+        assert_eq!(
+            bits3(q * RollVec3::new(0.5, -4.0, 8.0)),
+            [0x40BC_D37E, 0xC05A_A072, 0x40B9_EEFC]
+        );
+        assert_eq!(
+            bits4(q.nlerp(r, 0.25)),
+            [0x3DB4_1ECD, 0xBD9E_A0FE, 0x3E87_171A, 0x3F75_195D]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::vec;
+
+use bevy::{
+    ecs::{
+        entity::{UniqueEntityIter, UniqueEntityVec},
+        query::{QueryData, QueryFilter, QueryManyIter, QueryManyUniqueIter},
+        system::SystemParam,
+    },
+    prelude::*,
+};
+
+/// User-defined key for ordering entities in a [`RollQuery`].
+///
+/// Entities with a [`RollOrder`] are iterated before any other entities, in
+/// ascending order. Ties are broken by the rollback id (if any).
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[reflect(Component, Hash)]
+pub struct RollOrder(pub u64);
+
+/// A [`Query`] that iterates entities in a stable order, independent of
+/// archetype and entity allocation.
+///
+/// Regular query iteration order may differ after a rollback has despawned and
+/// respawned entities, which is a problem for systems with order-dependent
+/// side effects, such as spawning entities or drawing random numbers.
+///
+/// Entities are sorted by:
+/// 1. [`RollOrder`], if present
+/// 2. `bevy_ggrs` rollback id, if present (requires the `bevy_ggrs` feature)
+/// 3. [`Entity`], which is *not* stable across rollbacks.
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_roll_safe::prelude::*;
+/// #[derive(Component)]
+/// struct Spawner;
+///
+/// fn spawn_projectiles(mut commands: Commands, spawners: RollQuery<&Transform, With<Spawner>>) {
+///     // entities are always spawned in the same order
+///     for transform in spawners.iter() {
+///         commands.spawn(*transform);
+///     }
+/// }
+/// ```
+///
+/// Other [`Query`] methods are available through [`RollQuery::query`].
+///
+/// Note: `D` must not access [`RollOrder`] mutably.
+#[derive(SystemParam)]
+pub struct RollQuery<'w, 's, D: QueryData + 'static, F: QueryFilter + 'static = ()> {
+    query: Query<'w, 's, D, F>,
+    entities: Query<'w, 's, Entity, F>,
+    keys: Query<'w, 's, (Option<&'static RollOrder>, RollbackId)>,
+    #[cfg(feature = "bevy_ggrs")]
+    rollback_ordered: Option<Res<'w, bevy_ggrs::RollbackOrdered>>,
+}
+
+#[cfg(feature = "bevy_ggrs")]
+type RollbackId = Option<&'static bevy_ggrs::Rollback>;
+
+#[cfg(not(feature = "bevy_ggrs"))]
+type RollbackId = ();
+
+impl<'w, 's, D: QueryData + 'static, F: QueryFilter + 'static> RollQuery<'w, 's, D, F> {
+    /// Returns the matching entities in rollback-stable order.
+    pub fn sorted_entities(&self) -> Vec<Entity> {
+        let mut entities: Vec<(SortKey, Entity)> = self
+            .entities
+            .iter()
+            .map(|entity| (self.sort_key(entity), entity))
+            .collect();
+        entities.sort_unstable();
+        entities.into_iter().map(|(_, entity)| entity).collect()
+    }
+
+    /// Iterates over the query items in rollback-stable order.
+    pub fn iter(&self) -> QueryManyIter<'_, 's, D::ReadOnly, F, vec::IntoIter<Entity>> {
+        self.query.iter_many(self.sorted_entities())
+    }
+
+    /// Iterates mutably over the query items in rollback-stable order.
+    pub fn iter_mut(
+        &mut self,
+    ) -> QueryManyUniqueIter<'_, 's, D, F, UniqueEntityIter<vec::IntoIter<Entity>>> {
+        // SAFETY: entities come from a single query, so they are unique
+        let entities = unsafe { UniqueEntityVec::from_vec_unchecked(self.sorted_entities()) };
+        self.query.iter_many_unique_mut(entities)
+    }
+
+    /// Returns the underlying, unsorted, [`Query`].
+    pub fn query(&mut self) -> &mut Query<'w, 's, D, F> {
+        &mut self.query
+    }
+
+    fn sort_key(&self, entity: Entity) -> SortKey {
+        let Ok((order, _rollback)) = self.keys.get(entity) else {
+            return SortKey::Unordered(entity);
+        };
+
+        #[cfg(feature = "bevy_ggrs")]
+        let rollback_id = _rollback
+            .zip(self.rollback_ordered.as_ref())
+            .map(|(rollback, rollback_ordered)| rollback_ordered.order(*rollback));
+        #[cfg(not(feature = "bevy_ggrs"))]
+        let rollback_id = None;
+
+        match (order, rollback_id) {
+            (Some(order), rollback_id) => SortKey::Ordered(*order, rollback_id, entity),
+            (None, Some(rollback_id)) => SortKey::Rollback(rollback_id),
+            (None, None) => SortKey::Unordered(entity),
+        }
+    }
+}
+
+/// Variants are sorted in declaration order.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum SortKey {
+    Ordered(RollOrder, Option<u64>, Entity),
+    Rollback(u64),
+    Unordered(Entity),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component)]
+    struct Name(&'static str);
+
+    #[derive(Resource, Default)]
+    struct Visited(Vec<&'static str>);
+
+    fn visit(mut visited: ResMut<Visited>, names: RollQuery<&Name>) {
+        visited.0.extend(names.iter().map(|name| name.0));
+    }
+
+    #[test]
+    fn iterates_in_roll_order() {
+        let mut app = App::new();
+        app.init_resource::<Visited>().add_systems(Update, visit);
+
+        app.world_mut().spawn((Name("c"), RollOrder(3)));
+        app.world_mut().spawn((Name("a"), RollOrder(1)));
+        // different archetype
+        app.world_mut()
+            .spawn((Name("b"), RollOrder(2), Transform::default()));
+
+        app.update();
+
+        assert_eq!(app.world().resource::<Visited>().0, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    #[cfg(feature = "bevy_ggrs")]
+    fn iterates_mutably_in_rollback_order() {
+        use bevy_ggrs::{AddRollbackCommandExtension, SnapshotPlugin};
+
+        let mut app = App::new();
+        app.add_plugins(SnapshotPlugin)
+            .init_resource::<Visited>()
+            .add_systems(
+                Update,
+                |mut visited: ResMut<Visited>, mut names: RollQuery<&mut Name>| {
+                    visited.0.extend(names.iter_mut().map(|name| name.0));
+                },
+            );
+
+        let a = app.world_mut().spawn(Name("a")).id();
+        let b = app.world_mut().spawn(Name("b")).id();
+        let c = app.world_mut().spawn(Name("c")).id();
+        // rollback ids are handed out in a different order than entities
+        for entity in [c, a, b] {
+            app.world_mut().commands().entity(entity).add_rollback();
+        }
+        app.world_mut().flush();
+        app.world_mut().spawn((Name("first"), RollOrder(0)));
+        app.world_mut().spawn(Name("unordered"));
+
+        app.update();
+
+        assert_eq!(
+            app.world().resource::<Visited>().0,
+            vec!["first", "c", "a", "b", "unordered"]
+        );
+    }
+}
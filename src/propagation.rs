@@ -0,0 +1,196 @@
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+
+use crate::{RollbackPostUpdate, RollbackPreUpdate};
+
+#[cfg(feature = "bevy_ggrs")]
+type RollbackFilter = With<bevy_ggrs::Rollback>;
+
+#[cfg(not(feature = "bevy_ggrs"))]
+type RollbackFilter = ();
+
+/// System set containing the system that recomputes [`GlobalTransform`]s in
+/// the rollback schedules.
+///
+/// Systems in the same schedule that modify [`Transform`]s should be ordered
+/// before this set, and systems that read [`GlobalTransform`] after it.
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RollTransformPropagationSystems;
+
+/// Recomputes [`GlobalTransform`]s of rollback entities inside the rollback
+/// schedules.
+///
+/// Bevy only propagates transforms in [`PostUpdate`], so without this plugin,
+/// rollback systems reading [`GlobalTransform`] see data from the last
+/// rendered frame, which is wrong after a snapshot has been loaded, and when
+/// several rollback frames run in a single render frame.
+///
+/// By default, propagation runs in [`RollbackPostUpdate`], so
+/// [`GlobalTransform`] is correct at the start of the next frame (and when it
+/// is saved). Use [`RollTransformPropagationPlugin::with_pre_update`] to also
+/// propagate in [`RollbackPreUpdate`], e.g. if transforms are restored by
+/// something other than a snapshot load.
+///
+/// With the `bevy_ggrs` feature, only hierarchies with `Rollback` entities
+/// are updated, otherwise all of them are.
+///
+/// Unlike Bevy's propagation, this doesn't rely on change detection, which
+/// doesn't survive rollbacks, so it always recomputes all rollback
+/// hierarchies.
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_roll_safe::prelude::*;
+/// # use bevy_roll_safe::{RollTransformPropagationPlugin, RollTransformPropagationSystems};
+/// fn detect_hits(targets: Query<&GlobalTransform>) {
+///     // ...
+/// }
+///
+/// # fn start() {
+/// # let mut app = App::new();
+/// app.add_plugins((
+///     RollbackSchedulePlugin::new(FixedUpdate),
+///     RollTransformPropagationPlugin::default(),
+/// ))
+/// .add_systems(RollbackUpdate, detect_hits);
+/// # }
+/// ```
+#[derive(Default)]
+pub struct RollTransformPropagationPlugin {
+    pre_update: bool,
+}
+
+impl RollTransformPropagationPlugin {
+    /// Also propagate transforms in [`RollbackPreUpdate`].
+    pub fn with_pre_update(mut self) -> Self {
+        self.pre_update = true;
+        self
+    }
+}
+
+impl Plugin for RollTransformPropagationPlugin {
+    fn build(&self, app: &mut App) {
+        add_propagation(app, RollbackPostUpdate);
+
+        if self.pre_update {
+            add_propagation(app, RollbackPreUpdate);
+        }
+    }
+}
+
+fn add_propagation(app: &mut App, schedule: impl ScheduleLabel) {
+    app.add_systems(
+        schedule,
+        propagate_roll_transforms.in_set(RollTransformPropagationSystems),
+    );
+}
+
+/// Recomputes [`GlobalTransform`] for all hierarchies containing rollback
+/// entities.
+#[allow(clippy::type_complexity)]
+pub fn propagate_roll_transforms(
+    roots: Query<(Entity, Option<&ChildOf>), (With<GlobalTransform>, RollbackFilter)>,
+    parents: Query<&ChildOf>,
+    rollback: Query<(), RollbackFilter>,
+    children: Query<&Children>,
+    mut transforms: Query<(&Transform, &mut GlobalTransform)>,
+) {
+    for (entity, child_of) in &roots {
+        let parent_global = match child_of {
+            // the parent will propagate to us, if it's in a rollback hierarchy
+            Some(child_of) if in_rollback_hierarchy(child_of.parent(), &parents, &rollback) => {
+                continue;
+            }
+            Some(child_of) => transforms
+                .get(child_of.parent())
+                .map_or(GlobalTransform::IDENTITY, |(_, global)| *global),
+            None => GlobalTransform::IDENTITY,
+        };
+
+        propagate_recursive(entity, parent_global, &children, &mut transforms);
+    }
+}
+
+fn in_rollback_hierarchy(
+    mut entity: Entity,
+    parents: &Query<&ChildOf>,
+    rollback: &Query<(), RollbackFilter>,
+) -> bool {
+    loop {
+        if rollback.contains(entity) {
+            return true;
+        }
+        let Ok(child_of) = parents.get(entity) else {
+            return false;
+        };
+        entity = child_of.parent();
+    }
+}
+
+fn propagate_recursive(
+    entity: Entity,
+    parent_global: GlobalTransform,
+    children: &Query<&Children>,
+    transforms: &mut Query<(&Transform, &mut GlobalTransform)>,
+) {
+    let Ok((transform, mut global)) = transforms.get_mut(entity) else {
+        return;
+    };
+
+    let new_global = parent_global.mul_transform(*transform);
+    global.set_if_neq(new_global);
+
+    if let Ok(entity_children) = children.get(entity) {
+        for &child in entity_children {
+            propagate_recursive(child, new_global, children, transforms);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils::TestRollbackSchedule, RollbackSchedulePlugin, RollbackUpdate};
+
+    #[derive(Resource, Default)]
+    struct SeenX(Vec<f32>);
+
+    fn movement(mut query: Query<&mut Transform, Without<ChildOf>>) {
+        for mut transform in &mut query {
+            transform.translation.x += 1.0;
+        }
+    }
+
+    fn read_global(mut seen: ResMut<SeenX>, query: Query<&GlobalTransform, With<ChildOf>>) {
+        seen.0
+            .extend(query.iter().map(|global| global.translation().x));
+    }
+
+    #[test]
+    fn propagates_in_rollback_schedule() {
+        let mut app = App::new();
+        app.add_plugins((
+            RollbackSchedulePlugin::new(TestRollbackSchedule),
+            RollTransformPropagationPlugin::default(),
+        ))
+        .init_resource::<SeenX>()
+        .add_systems(RollbackUpdate, (movement, read_global));
+
+        let parent = app.world_mut().spawn(Transform::default()).id();
+        app.world_mut()
+            .spawn((Transform::from_xyz(0.5, 0.0, 0.0), ChildOf(parent)));
+
+        #[cfg(feature = "bevy_ggrs")]
+        {
+            use bevy_ggrs::AddRollbackCommandExtension;
+            app.world_mut().commands().entity(parent).add_rollback();
+            app.world_mut().flush();
+        }
+
+        // several rollback frames without any rendering in between
+        app.world_mut().run_schedule(TestRollbackSchedule);
+        app.world_mut().run_schedule(TestRollbackSchedule);
+        app.world_mut().run_schedule(TestRollbackSchedule);
+
+        assert_eq!(app.world().resource::<SeenX>().0, vec![0.0, 1.5, 2.5]);
+    }
+}
@@ -1,10 +1,26 @@
+use std::{sync::Arc, time::Duration};
+
 use bevy::{
     ecs::schedule::{
-        ExecutorKind, InternedScheduleLabel, LogLevel, ScheduleBuildSettings, ScheduleLabel,
+        ExecutorKind, InternedScheduleLabel, InternedSystemSet, LogLevel, ScheduleBuildSettings,
+        ScheduleLabel,
     },
+    platform::{collections::HashMap, time::Instant},
     prelude::*,
 };
 
+use crate::{
+    budget::add_budget,
+    change_detection::begin_roll_change_frame,
+    commands_check::{CommandsConflictPass, RollCommandsSystems},
+    diagnostics::RollbackTimings,
+    frame_count::{current_rollback_frame, RollbackFrameTracker},
+    increase_frame_count,
+    local::reset_roll_locals,
+    session::reset_frame_count,
+    RollFrameCount, RollLocals, RollbackBudget, RollbackBudgetPolicy,
+};
+
 /// Runs rollback-safe state transitions
 ///
 /// By default, it will be triggered each frame after [`RollbackPreUpdate`], but
@@ -46,14 +62,65 @@ pub struct RollbackUpdate;
 #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash, Default)]
 pub struct RollbackPostUpdate;
 
+/// Settings for one of the rollback schedules, see
+/// [`RollbackSchedulePlugin::with_executor_kind`] and
+/// [`RollbackSchedulePlugin::with_build_settings`].
+#[derive(Default, Clone)]
+struct ScheduleSettings {
+    executor_kind: Option<ExecutorKind>,
+    build_settings: Vec<EditBuildSettings>,
+}
+
+type EditBuildSettings = Arc<dyn Fn(&mut ScheduleBuildSettings) + Send + Sync>;
+
+/// Adds the rollback schedules ([`RollbackPreUpdate`],
+/// [`RollbackStateTransition`], [`RollbackUpdate`] and [`RollbackPostUpdate`])
+/// and runs them, in order, each time the given parent schedule runs.
+///
+/// The defaults can be adjusted with builder methods, to adopt only parts of
+/// the plugin:
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy::ecs::schedule::LogLevel;
+/// # use bevy_roll_safe::prelude::*;
+/// # fn start() {
+/// # let mut app = App::new();
+/// app.add_plugins(
+///     RollbackSchedulePlugin::new(FixedUpdate)
+///         .without_schedule(RollbackPreUpdate)
+///         .with_state_transitions_after(RollbackUpdate)
+///         .with_frame_count()
+///         .with_ambiguity_detection(LogLevel::Warn),
+/// );
+/// # }
+/// ```
 pub struct RollbackSchedulePlugin {
     schedule: InternedScheduleLabel,
+    order: Vec<InternedScheduleLabel>,
+    frame_count: bool,
+    ambiguity_detection: LogLevel,
+    check_commands_conflicts: bool,
+    allowed_commands_conflicts: Vec<(InternedSystemSet, InternedSystemSet)>,
+    schedule_settings: HashMap<InternedScheduleLabel, ScheduleSettings>,
+    budget: Option<RollbackBudget>,
 }
 
 impl RollbackSchedulePlugin {
     pub fn new(schedule: impl ScheduleLabel + 'static) -> Self {
         Self {
             schedule: schedule.intern(),
+            order: RollbackScheduleOrder::default().labels,
+            frame_count: false,
+            ambiguity_detection: LogLevel::Error,
+            check_commands_conflicts: false,
+            // the crate's own systems don't depend on each other's order
+            allowed_commands_conflicts: vec![(
+                RollCommandsSystems.intern(),
+                RollCommandsSystems.intern(),
+            )],
+            schedule_settings: default(),
+            budget: None,
         }
     }
 
@@ -61,29 +128,256 @@ impl RollbackSchedulePlugin {
     pub fn new_ggrs() -> Self {
         Self::new(bevy_ggrs::GgrsSchedule)
     }
+
+    /// Don't add or run the given rollback schedule.
+    pub fn without_schedule(mut self, label: impl ScheduleLabel) -> Self {
+        let label = label.intern();
+        self.order.retain(|l| *l != label);
+        self
+    }
+
+    /// Run [`RollbackStateTransition`] right after the given rollback
+    /// schedule, instead of after [`RollbackPreUpdate`].
+    pub fn with_state_transitions_after(self, label: impl ScheduleLabel) -> Self {
+        self.move_state_transitions(label.intern(), 1)
+    }
+
+    /// Run [`RollbackStateTransition`] right before the given rollback
+    /// schedule, instead of after [`RollbackPreUpdate`].
+    pub fn with_state_transitions_before(self, label: impl ScheduleLabel) -> Self {
+        self.move_state_transitions(label.intern(), 0)
+    }
+
+    fn move_state_transitions(mut self, label: InternedScheduleLabel, offset: usize) -> Self {
+        let state_transition = RollbackStateTransition.intern();
+        self.order.retain(|l| *l != state_transition);
+        let Some(index) = self.order.iter().position(|l| *l == label) else {
+            panic!("{label:?} is not one of the included rollback schedules");
+        };
+        self.order.insert(index + offset, state_transition);
+        self
+    }
+
+    /// Also add the [`RollFrameCount`] resource, and increase it at the start
+    /// of each rollback frame.
+    ///
+    /// With the `bevy_ggrs` feature, it's also registered for rollback and
+    /// checksumming, unless that was already done before adding this plugin.
+    pub fn with_frame_count(mut self) -> Self {
+        self.frame_count = true;
+        self
+    }
+
+    /// Sets the ambiguity detection level for all rollback schedules, unless
+    /// overridden with [`RollbackSchedulePlugin::with_build_settings`].
+    ///
+    /// Defaults to [`LogLevel::Error`], as ambiguous system order is a common
+    /// source of desyncs.
+    pub fn with_ambiguity_detection(mut self, level: LogLevel) -> Self {
+        self.ambiguity_detection = level;
+        self
+    }
+
+    /// Fail building the rollback schedules if two systems that issue
+    /// `Commands` are not ordered relative to each other.
+    ///
+    /// Commands are applied in system order, so unordered systems may apply
+    /// theirs in a different order after the schedule is rebuilt, e.g. in a
+    /// different build or on a different peer. Even systems that only spawn
+    /// entities conflict, since their entities would get `Rollback` ids in a
+    /// different order. Use
+    /// [`allow_commands_conflict`](Self::allow_commands_conflict) for pairs
+    /// whose commands are known to commute, e.g. ones inserting different
+    /// marker components.
+    pub fn with_commands_conflict_check(mut self) -> Self {
+        self.check_commands_conflicts = true;
+        self
+    }
+
+    /// Don't report conflicts found by
+    /// [`with_commands_conflict_check`](Self::with_commands_conflict_check)
+    /// between systems in `a` and systems in `b`, e.g. two systems known to
+    /// touch different entities.
+    ///
+    /// The systems added by this crate's plugins that issue commands are in
+    /// [`RollCommandsSystems`], which can be passed here too. Conflicts
+    /// between them are allowed by default.
+    ///
+    /// Schedule build passes can't see Bevy's `ambiguous_with`, so use this
+    /// to silence a specific pair instead.
+    pub fn allow_commands_conflict<M1, M2>(
+        mut self,
+        a: impl IntoSystemSet<M1>,
+        b: impl IntoSystemSet<M2>,
+    ) -> Self {
+        self.allowed_commands_conflicts
+            .push((a.into_system_set().intern(), b.into_system_set().intern()));
+        self
+    }
+
+    /// Sets the executor for one of the rollback schedules, e.g.
+    /// [`RollbackUpdate`], or for the parent schedule that runs them.
+    ///
+    /// By default, the rollback schedules use Bevy's default executor. Heavy
+    /// schedules may benefit from [`ExecutorKind::MultiThreaded`], while small
+    /// ones are usually faster with [`ExecutorKind::SingleThreaded`].
+    ///
+    /// The parent schedule defaults to [`ExecutorKind::SingleThreaded`], as it
+    /// usually only facilitates running the rollback schedules. Override it if
+    /// it also contains heavy systems of its own, e.g. when using
+    /// [`FixedUpdate`].
+    pub fn with_executor_kind(
+        mut self,
+        label: impl ScheduleLabel,
+        executor_kind: ExecutorKind,
+    ) -> Self {
+        self.schedule_settings
+            .entry(label.intern())
+            .or_default()
+            .executor_kind = Some(executor_kind);
+        self
+    }
+
+    /// Adjusts the build settings, such as ambiguity detection and automatic
+    /// `apply_deferred` insertion, for one of the rollback schedules.
+    ///
+    /// `edit` is applied to the rollback defaults, which are
+    /// [`ScheduleBuildSettings::default`] with ambiguity detection set
+    /// according to [`RollbackSchedulePlugin::with_ambiguity_detection`], so
+    /// only the fields it changes are overridden.
+    ///
+    /// ```rust
+    /// # use bevy::prelude::*;
+    /// # use bevy_roll_safe::prelude::*;
+    /// # let mut app = App::new();
+    /// app.add_plugins(
+    ///     RollbackSchedulePlugin::new(FixedUpdate).with_build_settings(
+    ///         RollbackUpdate,
+    ///         |settings| settings.auto_insert_apply_deferred = false,
+    ///     ),
+    /// );
+    /// ```
+    pub fn with_build_settings(
+        mut self,
+        label: impl ScheduleLabel,
+        edit: impl Fn(&mut ScheduleBuildSettings) + Send + Sync + 'static,
+    ) -> Self {
+        self.schedule_settings
+            .entry(label.intern())
+            .or_default()
+            .build_settings
+            .push(Arc::new(edit));
+        self
+    }
+
+    /// Measure the time spent in the rollback schedules in each render frame,
+    /// and apply `policy` when it exceeds `budget`.
+    ///
+    /// The time spent and remaining budget can be read from the
+    /// [`RollbackBudget`] resource.
+    pub fn with_budget(mut self, budget: Duration, policy: RollbackBudgetPolicy) -> Self {
+        self.budget = Some(RollbackBudget::new(budget, policy));
+        self
+    }
 }
 
 impl Plugin for RollbackSchedulePlugin {
     fn build(&self, app: &mut App) {
+        for (label, settings) in &self.schedule_settings {
+            if *label == self.schedule {
+                if !settings.build_settings.is_empty() {
+                    warn!("{label:?} is not a rollback schedule, ignoring its build settings");
+                }
+            } else if !self.order.contains(label) {
+                warn!("{label:?} is not a rollback schedule, ignoring its settings");
+            }
+        }
+
         // simple "facilitator" schedules benefit from simpler single threaded scheduling
-        let mut rollback_schedule = Schedule::new(self.schedule);
-        rollback_schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        let parent_executor_kind = self
+            .schedule_settings
+            .get(&self.schedule)
+            .and_then(|settings| settings.executor_kind)
+            .unwrap_or(ExecutorKind::SingleThreaded);
+        app.edit_schedule(self.schedule, |schedule| {
+            schedule.set_executor_kind(parent_executor_kind);
+        });
+
+        for &label in &self.order {
+            let settings = self
+                .schedule_settings
+                .get(&label)
+                .cloned()
+                .unwrap_or_default();
+
+            let mut build_settings = ScheduleBuildSettings {
+                ambiguity_detection: self.ambiguity_detection,
+                ..default()
+            };
+            for edit in &settings.build_settings {
+                edit(&mut build_settings);
+            }
 
-        for label in RollbackScheduleOrder::default().labels {
             app.edit_schedule(label, |schedule| {
-                schedule.set_build_settings(ScheduleBuildSettings {
-                    ambiguity_detection: LogLevel::Error,
-                    ..default()
-                });
+                schedule
+                    .set_build_settings(build_settings.clone())
+                    // commands are always applied before the next schedule runs
+                    .set_apply_final_deferred(true);
+
+                if let Some(executor_kind) = settings.executor_kind {
+                    schedule.set_executor_kind(executor_kind);
+                }
             });
+
+            if self.check_commands_conflicts {
+                app.edit_schedule(label, |schedule| {
+                    schedule.add_build_pass(CommandsConflictPass {
+                        allowed: self.allowed_commands_conflicts.clone(),
+                    });
+                });
+            }
+        }
+
+        app.insert_resource(RollbackScheduleOrder {
+            labels: self.order.clone(),
+        })
+        .init_resource::<RollLocals>()
+        .init_resource::<RollbackFrameTracker>()
+        .add_systems(self.schedule, run_schedules)
+        .add_observer(reset_frame_count)
+        .add_observer(reset_roll_locals);
+
+        if self.frame_count {
+            app.init_resource::<RollFrameCount>()
+                .add_systems(self.schedule, increase_frame_count.before(run_schedules));
+
+            #[cfg(feature = "bevy_ggrs")]
+            {
+                use bevy_ggrs::{
+                    CopyStrategy, ResourceChecksumPlugin, ResourceSnapshotPlugin, RollbackApp,
+                };
+                if !app.is_plugin_added::<ResourceSnapshotPlugin<CopyStrategy<RollFrameCount>>>() {
+                    app.rollback_resource_with_copy::<RollFrameCount>();
+                }
+                if !app.is_plugin_added::<ResourceChecksumPlugin<RollFrameCount>>() {
+                    app.checksum_resource_with_hash::<RollFrameCount>();
+                }
+            }
         }
 
-        app.insert_resource(RollbackScheduleOrder::default())
-            .add_systems(self.schedule, run_schedules);
+        if let Some(budget) = &self.budget {
+            add_budget(app, budget.clone());
+        }
+
+        #[cfg(feature = "bevy_ggrs")]
+        {
+            use crate::local::RollLocalsStrategy;
+            use bevy_ggrs::ResourceSnapshotPlugin;
+            app.add_plugins(ResourceSnapshotPlugin::<RollLocalsStrategy>::default());
+        }
     }
 }
 
-//TODO: expose in public API?
 /// Defines the schedules to be run for the rollback schedule, including
 /// their order.
 #[derive(Resource, Debug)]
@@ -106,12 +400,47 @@ impl Default for RollbackScheduleOrder {
 }
 
 fn run_schedules(world: &mut World) {
+    // only measure when `RollbackDiagnosticsPlugin` or a budget is added
+    let timed = world.contains_resource::<RollbackTimings>();
+    let budgeted = world.contains_resource::<RollbackBudget>();
+    let frame = current_rollback_frame(world);
+    let mut tracker = world.resource_mut::<RollbackFrameTracker>();
+    tracker.begin_frame(frame);
+    let resimulation = tracker.is_resimulation();
+    begin_roll_change_frame(world);
+    let start = (timed || budgeted).then(Instant::now);
+
     world.resource_scope(|world, order: Mut<RollbackScheduleOrder>| {
         for label in &order.labels {
             trace!("Running rollback schedule: {:?}", label);
+            let phase_start = timed.then(Instant::now);
             let _ = world.try_run_schedule(*label);
+            // also apply commands queued directly on the world, e.g. by hooks
+            // or observers, so the next schedule sees them
+            world.flush();
+
+            if let (Some(phase_start), Some(mut timings)) =
+                (phase_start, world.get_resource_mut::<RollbackTimings>())
+            {
+                timings.record_phase(*label, phase_start.elapsed());
+            }
         }
     });
+
+    world.resource_mut::<RollbackFrameTracker>().end_frame();
+
+    let Some(start) = start else {
+        return;
+    };
+    let elapsed = start.elapsed();
+
+    if let Some(mut timings) = world.get_resource_mut::<RollbackTimings>() {
+        timings.record_frame(resimulation, elapsed);
+    }
+
+    if let Some(mut budget) = world.get_resource_mut::<RollbackBudget>() {
+        budget.record(elapsed);
+    }
 }
 
 #[cfg(test)]
@@ -265,4 +594,302 @@ mod tests {
             NextState::Unchanged,
         ));
     }
+
+    #[test]
+    #[cfg(feature = "bevy_ggrs")]
+    fn records_state_history() {
+        use crate::{
+            test_utils::{advance_and_save, ggrs_test_app},
+            RollStateHistory,
+        };
+        use bevy_ggrs::{LoadWorld, RollbackFrameCount, SaveWorld};
+
+        let mut app = ggrs_test_app();
+        app.init_ggrs_state::<GameplayState>()
+            .init_resource::<RollStateHistory<GameplayState>>()
+            .add_systems(
+                RollbackUpdate,
+                set_game_over_state.run_if(in_state(GameplayState::InRound)),
+            );
+
+        app.world_mut().run_schedule(SaveWorld);
+        advance_and_save(app.world_mut());
+        advance_and_save(app.world_mut());
+
+        // roll back to frame 0 and resimulate
+        app.world_mut().resource_mut::<RollbackFrameCount>().0 = 0;
+        app.world_mut().run_schedule(LoadWorld);
+        advance_and_save(app.world_mut());
+        advance_and_save(app.world_mut());
+
+        let history = app.world().resource::<RollStateHistory<GameplayState>>();
+        let records: Vec<_> = history
+            .records()
+            .map(|r| {
+                (
+                    r.frame,
+                    r.exited.clone(),
+                    r.entered.clone(),
+                    r.was_resimulation,
+                )
+            })
+            .collect();
+
+        use GameplayState::*;
+        assert_eq!(
+            records,
+            vec![
+                (Some(1), None, Some(InRound), false),
+                (Some(2), Some(InRound), Some(GameOver), false),
+                (Some(1), None, Some(InRound), true),
+                (Some(2), Some(InRound), Some(GameOver), true),
+            ]
+        );
+        assert!(history
+            .dump()
+            .contains("frame 2: Some(InRound) -> Some(GameOver) (resimulation)"));
+    }
+
+    #[derive(Component)]
+    struct Health(u32);
+
+    fn spawn_health(mut commands: Commands, players: Query<&Health>) {
+        for health in &players {
+            commands.spawn(Health(health.0));
+        }
+    }
+
+    fn despawn_dead(mut commands: Commands, players: Query<(Entity, &Health)>) {
+        for (entity, health) in &players {
+            if health.0 == 0 {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+
+    fn commands_check_result(ordered: bool) -> Result<(), bevy::ecs::schedule::ScheduleBuildError> {
+        let mut app = App::new();
+        app.add_plugins(RollbackSchedulePlugin::new(Update).with_commands_conflict_check());
+        if ordered {
+            app.add_systems(RollbackUpdate, (spawn_health, despawn_dead).chain());
+        } else {
+            app.add_systems(RollbackUpdate, (spawn_health, despawn_dead));
+        }
+
+        app.world_mut()
+            .schedule_scope(RollbackUpdate, |world, schedule| schedule.initialize(world))
+    }
+
+    #[test]
+    fn commands_conflict_check() {
+        assert!(commands_check_result(false).is_err());
+        assert!(commands_check_result(true).is_ok());
+    }
+
+    fn spawn_enemy(mut commands: Commands) {
+        commands.spawn(Health(1));
+    }
+
+    fn spawn_pickup(mut commands: Commands) {
+        commands.spawn(Name::new("pickup"));
+    }
+
+    #[test]
+    fn spawn_only_commands_conflict() {
+        let mut app = App::new();
+        app.add_plugins(RollbackSchedulePlugin::new(Update).with_commands_conflict_check())
+            .add_systems(RollbackUpdate, (spawn_enemy, spawn_pickup));
+
+        let result = app
+            .world_mut()
+            .schedule_scope(RollbackUpdate, |world, schedule| schedule.initialize(world));
+        assert!(result.is_err());
+    }
+
+    fn initialize_rollback_schedules(app: &mut App) -> Result<(), Vec<String>> {
+        let errors: Vec<_> = RollbackScheduleOrder::default()
+            .labels
+            .into_iter()
+            .filter_map(|label| {
+                app.world_mut()
+                    .schedule_scope(label, |world, schedule| schedule.initialize(world))
+                    .err()
+                    .map(|error| format!("{label:?}: {error}"))
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn add_crate_plugins(app: &mut App, plugin: RollbackSchedulePlugin) {
+        use crate::{
+            RollChangeDetectionPlugin, RollLifetimePlugin, RollTransformPropagationPlugin,
+            RollbackInterpolationPlugin,
+        };
+
+        app.add_plugins((
+            plugin,
+            RollChangeDetectionPlugin::<Health>::default(),
+            RollChangeDetectionPlugin::<Transform>::default(),
+            RollbackInterpolationPlugin::<Transform>::default(),
+            RollLifetimePlugin,
+            RollTransformPropagationPlugin::default(),
+        ));
+
+        #[cfg(feature = "audio")]
+        app.add_plugins(crate::RollbackAudioPlugin);
+    }
+
+    #[test]
+    fn crate_plugins_pass_commands_conflict_check() {
+        let mut app = App::new();
+        add_crate_plugins(
+            &mut app,
+            RollbackSchedulePlugin::new(Update).with_commands_conflict_check(),
+        );
+        assert_eq!(initialize_rollback_schedules(&mut app), Ok(()));
+
+        // but they are still checked against user systems
+        let mut app = App::new();
+        add_crate_plugins(
+            &mut app,
+            RollbackSchedulePlugin::new(Update).with_commands_conflict_check(),
+        );
+        app.add_systems(RollbackPostUpdate, spawn_enemy);
+        assert!(initialize_rollback_schedules(&mut app).is_err());
+
+        let mut app = App::new();
+        add_crate_plugins(
+            &mut app,
+            RollbackSchedulePlugin::new(Update)
+                .with_commands_conflict_check()
+                .allow_commands_conflict(RollCommandsSystems, spawn_enemy),
+        );
+        app.add_systems(RollbackPostUpdate, spawn_enemy);
+        assert_eq!(initialize_rollback_schedules(&mut app), Ok(()));
+    }
+
+    #[test]
+    fn allowed_commands_conflict() {
+        let mut app = App::new();
+        app.add_plugins(
+            RollbackSchedulePlugin::new(Update)
+                .with_commands_conflict_check()
+                .allow_commands_conflict(spawn_health, despawn_dead),
+        )
+        .add_systems(RollbackUpdate, (spawn_health, despawn_dead));
+
+        let result = app
+            .world_mut()
+            .schedule_scope(RollbackUpdate, |world, schedule| schedule.initialize(world));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn per_schedule_settings() {
+        let mut app = App::new();
+        app.add_plugins(
+            RollbackSchedulePlugin::new(Update)
+                .with_executor_kind(RollbackPreUpdate, ExecutorKind::SingleThreaded)
+                .with_ambiguity_detection(LogLevel::Warn)
+                .with_build_settings(RollbackUpdate, |settings| {
+                    settings.auto_insert_apply_deferred = false;
+                })
+                .with_build_settings(RollbackPostUpdate, |settings| {
+                    settings.ambiguity_detection = LogLevel::Ignore;
+                }),
+        );
+
+        let schedules = app.world().resource::<Schedules>();
+        assert_eq!(
+            schedules.get(Update).unwrap().get_executor_kind(),
+            ExecutorKind::SingleThreaded
+        );
+
+        let pre_update = schedules.get(RollbackPreUpdate).unwrap();
+        assert_eq!(pre_update.get_executor_kind(), ExecutorKind::SingleThreaded);
+        assert_eq!(
+            pre_update.get_build_settings().ambiguity_detection,
+            LogLevel::Warn
+        );
+
+        // overriding one field keeps the rollback defaults for the others
+        let update = schedules.get(RollbackUpdate).unwrap();
+        assert!(!update.get_build_settings().auto_insert_apply_deferred);
+        assert_eq!(
+            update.get_build_settings().ambiguity_detection,
+            LogLevel::Warn
+        );
+
+        let post_update = schedules.get(RollbackPostUpdate).unwrap();
+        assert!(post_update.get_build_settings().auto_insert_apply_deferred);
+        assert_eq!(
+            post_update.get_build_settings().ambiguity_detection,
+            LogLevel::Ignore
+        );
+    }
+
+    #[test]
+    fn parent_executor_kind() {
+        let mut app = App::new();
+        app.add_plugins(
+            RollbackSchedulePlugin::new(FixedUpdate)
+                .with_executor_kind(FixedUpdate, ExecutorKind::MultiThreaded),
+        );
+
+        let schedules = app.world().resource::<Schedules>();
+        assert_eq!(
+            schedules.get(FixedUpdate).unwrap().get_executor_kind(),
+            ExecutorKind::MultiThreaded
+        );
+    }
+
+    #[derive(Resource, Default)]
+    struct Ran(Vec<&'static str>);
+
+    #[test]
+    fn builder_configures_schedule_order() {
+        let mut app = App::new();
+        app.add_plugins(
+            RollbackSchedulePlugin::new(Update)
+                .without_schedule(RollbackPreUpdate)
+                .with_state_transitions_after(RollbackUpdate)
+                .with_frame_count(),
+        )
+        .init_resource::<Ran>()
+        .add_systems(RollbackPreUpdate, |mut ran: ResMut<Ran>| ran.0.push("pre"))
+        .add_systems(RollbackStateTransition, |mut ran: ResMut<Ran>| {
+            ran.0.push("state")
+        })
+        .add_systems(RollbackUpdate, |mut ran: ResMut<Ran>| ran.0.push("update"))
+        .add_systems(RollbackPostUpdate, |mut ran: ResMut<Ran>| {
+            ran.0.push("post")
+        });
+
+        app.update();
+        app.update();
+
+        assert_eq!(
+            app.world().resource::<Ran>().0,
+            vec!["update", "state", "post", "update", "state", "post"]
+        );
+        assert_eq!(app.world().resource::<RollFrameCount>().0, 2);
+    }
+
+    #[test]
+    #[cfg(feature = "bevy_ggrs")]
+    fn frame_count_already_registered_for_rollback() {
+        use bevy_ggrs::RollbackApp;
+
+        let mut app = App::new();
+        app.rollback_resource_with_copy::<RollFrameCount>()
+            .checksum_resource_with_hash::<RollFrameCount>()
+            .add_plugins(RollbackSchedulePlugin::new(Update).with_frame_count());
+
+        app.update();
+        assert_eq!(app.world().resource::<RollFrameCount>().0, 1);
+    }
 }
@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+
+use crate::{frame_count::RollbackFrameTracker, RollFrameCount};
+
+/// Resets rollback state when a new session starts in the same [`App`].
+///
+/// Trigger it with `commands.trigger(RollbackSessionReset)` after a match has
+/// ended, and before the next one starts. It resets:
+///
+/// - all states added with [`RollApp`](crate::RollApp), see
+///   [`reset_roll_state`](crate::reset_roll_state)
+/// - [`RollFrameCount`]
+/// - [`RollLocal`](crate::RollLocal) values, back to their initial value
+/// - rollback audio, if the `RollbackAudioPlugin` is added
+/// - the frames seen so far, used to tell resimulations apart by
+///   [`RollStateHistory`](crate::RollStateHistory), diagnostics,
+///   interpolation and the `DesyncDetectorPlugin`
+///
+/// `bevy_ggrs` resets its own frame count when there is no session.
+///
+/// Requires the [`RollbackSchedulePlugin`](crate::RollbackSchedulePlugin).
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct RollbackSessionReset;
+
+pub(crate) fn reset_frame_count(
+    _reset: On<RollbackSessionReset>,
+    frame_count: Option<ResMut<RollFrameCount>>,
+    mut tracker: ResMut<RollbackFrameTracker>,
+) {
+    if let Some(mut frame_count) = frame_count {
+        *frame_count = default();
+    }
+    *tracker = default();
+}
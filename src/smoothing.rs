@@ -0,0 +1,290 @@
+use bevy::{prelude::*, transform::TransformSystems};
+use bevy_ggrs::{AdvanceWorld, LoadWorld, LoadWorldSystems, RollbackFrameCount, SaveWorld};
+
+use crate::interpolation;
+
+/// Smooths out visual snapping when a rollback corrects an entity's
+/// [`Transform`].
+///
+/// When the world is loaded, the predicted transform is remembered. Once the
+/// same frame has been resimulated, the difference to the corrected transform
+/// becomes a visual offset, which is applied in [`PostUpdate`] and decays to
+/// zero over [`RollbackSmoothing::frames`] rendered frames.
+///
+/// The offset is removed again in [`First`], so rollback systems only ever see
+/// the simulated transform.
+///
+/// Not registered for rollback, so entities respawned by `bevy_ggrs` when
+/// loading a snapshot come back without it, and without the offset they had.
+/// Add it as a required component of a rolled back component if that
+/// matters.
+///
+/// Requires the [`RollbackSmoothingPlugin`].
+#[derive(Component, Debug, Clone)]
+pub struct RollbackSmoothing {
+    /// The number of rendered frames it takes for a correction to disappear.
+    pub frames: u32,
+    translation_offset: Vec3,
+    rotation_offset: Quat,
+    remaining_frames: u32,
+    /// Frame and transform before the last load, until it's resimulated
+    predicted: Option<(i32, Transform)>,
+    /// Transform before the offset was applied
+    simulated: Option<Transform>,
+}
+
+impl Default for RollbackSmoothing {
+    fn default() -> Self {
+        Self::new(10)
+    }
+}
+
+impl RollbackSmoothing {
+    pub fn new(frames: u32) -> Self {
+        Self {
+            frames,
+            translation_offset: Vec3::ZERO,
+            rotation_offset: Quat::IDENTITY,
+            remaining_frames: 0,
+            predicted: None,
+            simulated: None,
+        }
+    }
+
+    /// The visual translation offset currently applied.
+    pub fn translation_offset(&self) -> Vec3 {
+        self.translation_offset
+    }
+
+    /// The visual rotation offset currently applied.
+    pub fn rotation_offset(&self) -> Quat {
+        self.rotation_offset
+    }
+}
+
+/// The rollback frame the world is currently at.
+///
+/// `bevy_ggrs` sets [`RollbackFrameCount`] to the loaded frame before running
+/// [`LoadWorld`], so it has to be remembered before that.
+#[derive(Resource, Default, Debug)]
+struct SimulatedFrame(i32);
+
+/// Enables [`RollbackSmoothing`].
+///
+/// Compatible with the [`RollbackInterpolationPlugin`](crate::RollbackInterpolationPlugin),
+/// in which case the offset is applied on top of the interpolated transform.
+pub struct RollbackSmoothingPlugin;
+
+impl Plugin for RollbackSmoothingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimulatedFrame>()
+            .add_systems(AdvanceWorld, track_simulated_frame)
+            .add_systems(
+                LoadWorld,
+                remember_predictions.before(LoadWorldSystems::Entity),
+            )
+            .add_systems(SaveWorld, detect_corrections)
+            .add_systems(
+                First,
+                remove_offsets.before(interpolation::restore_current::<Transform>),
+            )
+            .add_systems(
+                PostUpdate,
+                apply_offsets
+                    .after(interpolation::interpolate::<Transform>)
+                    .before(TransformSystems::Propagate),
+            );
+    }
+}
+
+fn track_simulated_frame(frame: Res<RollbackFrameCount>, mut simulated: ResMut<SimulatedFrame>) {
+    simulated.0 = frame.0;
+}
+
+fn remember_predictions(
+    simulated_frame: Res<SimulatedFrame>,
+    mut query: Query<(&Transform, &mut RollbackSmoothing)>,
+) {
+    for (transform, mut smoothing) in &mut query {
+        // if rolling back again before reaching the predicted frame, keep the
+        // original prediction, since that's what is being shown
+        if smoothing.predicted.is_none() {
+            smoothing.predicted = Some((simulated_frame.0, *transform));
+        }
+    }
+}
+
+fn detect_corrections(
+    frame: Res<RollbackFrameCount>,
+    mut simulated_frame: ResMut<SimulatedFrame>,
+    mut query: Query<(&Transform, &mut RollbackSmoothing)>,
+) {
+    simulated_frame.0 = frame.0;
+
+    for (transform, mut smoothing) in &mut query {
+        let Some((predicted_frame, predicted)) = smoothing.predicted else {
+            continue;
+        };
+
+        if predicted_frame > frame.0 {
+            continue;
+        }
+
+        smoothing.predicted = None;
+
+        if predicted_frame < frame.0 {
+            // shouldn't happen, but don't compare different frames
+            continue;
+        }
+
+        smoothing.translation_offset += predicted.translation - transform.translation;
+        smoothing.rotation_offset =
+            (smoothing.rotation_offset * predicted.rotation * transform.rotation.inverse())
+                .normalize();
+        smoothing.remaining_frames = smoothing.frames;
+    }
+}
+
+fn apply_offsets(mut query: Query<(&mut Transform, &mut RollbackSmoothing)>) {
+    for (mut transform, mut smoothing) in &mut query {
+        if smoothing.remaining_frames == 0 {
+            smoothing.translation_offset = Vec3::ZERO;
+            smoothing.rotation_offset = Quat::IDENTITY;
+            continue;
+        }
+
+        smoothing.simulated = Some(*transform);
+        transform.translation += smoothing.translation_offset;
+        transform.rotation = smoothing.rotation_offset * transform.rotation;
+
+        let s = 1.0 / smoothing.remaining_frames as f32;
+        smoothing.translation_offset = smoothing.translation_offset.lerp(Vec3::ZERO, s);
+        smoothing.rotation_offset = smoothing.rotation_offset.slerp(Quat::IDENTITY, s);
+        smoothing.remaining_frames -= 1;
+    }
+}
+
+fn remove_offsets(mut query: Query<(&mut Transform, &mut RollbackSmoothing)>) {
+    for (mut transform, mut smoothing) in &mut query {
+        if let Some(simulated) = smoothing.simulated.take() {
+            *transform = simulated;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ggrs::{AddRollbackCommandExtension, AdvanceWorld, RollbackApp, SnapshotPlugin};
+
+    /// Not rolled back, so changing it causes a correction
+    #[derive(Resource)]
+    struct Speed(f32);
+
+    fn movement(speed: Res<Speed>, mut query: Query<&mut Transform>) {
+        for mut transform in &mut query {
+            transform.translation.x += speed.0;
+        }
+    }
+
+    /// Saves the current frame and advances to the next, in the same order
+    /// as `bevy_ggrs`.
+    fn advance(world: &mut World) {
+        world.run_schedule(SaveWorld);
+        world.resource_mut::<RollbackFrameCount>().0 += 1;
+        world.run_schedule(AdvanceWorld);
+    }
+
+    /// Loads the given frame and resimulates the frame after it.
+    fn rollback(world: &mut World, frame: i32) {
+        world.resource_mut::<RollbackFrameCount>().0 = frame;
+        world.run_schedule(LoadWorld);
+        world.resource_mut::<RollbackFrameCount>().0 += 1;
+        world.run_schedule(AdvanceWorld);
+    }
+
+    fn setup(frames: u32) -> App {
+        let mut app = App::new();
+        app.add_plugins((SnapshotPlugin, RollbackSmoothingPlugin))
+            .rollback_component_with_copy::<Transform>()
+            .insert_resource(Speed(1.0))
+            .add_systems(AdvanceWorld, movement);
+
+        app.world_mut()
+            .commands()
+            .spawn((Transform::default(), RollbackSmoothing::new(frames)))
+            .add_rollback();
+        app.world_mut().flush();
+        app
+    }
+
+    fn smoothing(app: &mut App) -> RollbackSmoothing {
+        let mut query = app.world_mut().query::<&RollbackSmoothing>();
+        query.single(app.world()).unwrap().clone()
+    }
+
+    #[test]
+    fn smooths_corrections() {
+        let mut app = setup(2);
+
+        for _ in 0..3 {
+            advance(app.world_mut());
+        }
+
+        // resimulate frames 2 and 3 with a different speed
+        app.world_mut().resource_mut::<Speed>().0 = 2.0;
+        rollback(app.world_mut(), 1);
+        advance(app.world_mut());
+        app.world_mut().run_schedule(SaveWorld);
+
+        let mut query = app.world_mut().query::<&Transform>();
+        let mut x = |app: &mut App| query.single(app.world()).unwrap().translation.x;
+
+        assert_eq!(x(&mut app), 5.0);
+
+        let mut shown = Vec::new();
+        for _ in 0..3 {
+            app.world_mut().run_schedule(PostUpdate);
+            shown.push(x(&mut app));
+            app.world_mut().run_schedule(First);
+            assert_eq!(x(&mut app), 5.0);
+        }
+
+        assert_eq!(shown, vec![3.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn deterministic_rollback_has_no_offset() {
+        let mut app = setup(2);
+
+        for _ in 0..3 {
+            advance(app.world_mut());
+        }
+
+        rollback(app.world_mut(), 1);
+        advance(app.world_mut());
+        advance(app.world_mut());
+
+        let smoothing = smoothing(&mut app);
+        assert!(smoothing.predicted.is_none());
+        assert_eq!(smoothing.translation_offset(), Vec3::ZERO);
+    }
+
+    #[test]
+    fn smooths_one_frame_rollbacks() {
+        let mut app = setup(2);
+
+        for _ in 0..2 {
+            advance(app.world_mut());
+        }
+
+        // resimulate frame 2 with a different speed
+        app.world_mut().resource_mut::<Speed>().0 = 2.0;
+        rollback(app.world_mut(), 1);
+        app.world_mut().run_schedule(SaveWorld);
+
+        let smoothing = smoothing(&mut app);
+        assert!(smoothing.predicted.is_none());
+        assert_eq!(smoothing.translation_offset(), Vec3::new(-1.0, 0.0, 0.0));
+    }
+}
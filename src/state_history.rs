@@ -0,0 +1,145 @@
+use std::{collections::VecDeque, fmt::Write};
+
+use bevy::prelude::*;
+
+use crate::frame_count::{current_rollback_frame, RollbackFrameTracker};
+
+/// A single transition recorded in [`RollStateHistory`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RollStateTransitionRecord<S: States> {
+    /// The rollback frame the transition happened in, if a frame counter is
+    /// available.
+    pub frame: Option<u32>,
+    /// The state that was exited, `None` when the initial state was entered,
+    /// or an optional state was absent.
+    pub exited: Option<S>,
+    /// The state that was entered, `None` when an optional state was removed.
+    pub entered: Option<S>,
+    /// Whether the frame had already been simulated before, i.e. this
+    /// transition happened while resimulating after a rollback.
+    pub was_resimulation: bool,
+}
+
+/// Debug log of rollback state transitions for the state `S`.
+///
+/// Not rolled back, so it includes transitions from both the original
+/// simulation and any resimulations. Insert the resource to enable recording:
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_roll_safe::prelude::*;
+/// # use bevy_roll_safe::RollStateHistory;
+/// # #[derive(States, Hash, Default, Debug, Eq, PartialEq, Clone)]
+/// # enum GameplayState { #[default] InRound, GameOver }
+/// # fn start() {
+/// # let mut app = App::new();
+/// app.init_roll_state::<GameplayState>()
+///     .init_resource::<RollStateHistory<GameplayState>>();
+/// # }
+///
+/// fn dump_history(history: Res<RollStateHistory<GameplayState>>) {
+///     info!("{}", history.dump());
+/// }
+/// ```
+///
+/// The frame is read from `bevy_ggrs`' `RollbackFrameCount` if available, or
+/// [`RollFrameCount`](crate::RollFrameCount) otherwise.
+#[derive(Resource, Debug)]
+pub struct RollStateHistory<S: States> {
+    max_len: usize,
+    records: VecDeque<RollStateTransitionRecord<S>>,
+}
+
+impl<S: States> Default for RollStateHistory<S> {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl<S: States> RollStateHistory<S> {
+    /// Creates a history that keeps at most `max_len` transitions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_len` is zero.
+    pub fn new(max_len: usize) -> Self {
+        assert!(max_len > 0, "RollStateHistory needs room for a transition");
+        Self {
+            max_len,
+            records: VecDeque::with_capacity(max_len),
+        }
+    }
+
+    /// Recorded transitions, oldest first.
+    pub fn records(&self) -> impl Iterator<Item = &RollStateTransitionRecord<S>> {
+        self.records.iter()
+    }
+
+    /// Removes all recorded transitions.
+    ///
+    /// Resimulations are detected independently of the history, so transitions
+    /// recorded after clearing are still marked as resimulated when appropriate.
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    /// Formats the history as text, one transition per line, suitable for
+    /// attaching to bug reports.
+    pub fn dump(&self) -> String {
+        let mut dump = format!("{} transitions:\n", std::any::type_name::<S>());
+        for record in &self.records {
+            let frame = record
+                .frame
+                .map_or_else(|| "?".to_string(), |frame| frame.to_string());
+            let resimulation = if record.was_resimulation {
+                " (resimulation)"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                dump,
+                "frame {frame}: {:?} -> {:?}{resimulation}",
+                record.exited, record.entered
+            );
+        }
+        dump
+    }
+
+    fn push(
+        &mut self,
+        frame: Option<u32>,
+        was_resimulation: bool,
+        exited: Option<S>,
+        entered: Option<S>,
+    ) {
+        if self.records.len() == self.max_len {
+            self.records.pop_front();
+        }
+
+        self.records.push_back(RollStateTransitionRecord {
+            frame,
+            exited,
+            entered,
+            was_resimulation,
+        });
+    }
+}
+
+/// Records a transition, if history is recorded for `S`.
+pub(crate) fn record_transition<S: States>(
+    world: &mut World,
+    exited: Option<S>,
+    entered: Option<S>,
+) {
+    if !world.contains_resource::<RollStateHistory<S>>() {
+        return;
+    }
+    // transitions outside the rollback schedules are never resimulated
+    let (frame, was_resimulation) = match world.get_resource::<RollbackFrameTracker>() {
+        Some(tracker) if tracker.in_frame() => (tracker.current(), tracker.is_resimulation()),
+        _ => (current_rollback_frame(world), false),
+    };
+    world
+        .resource_mut::<RollStateHistory<S>>()
+        .push(frame, was_resimulation, exited, entered);
+}
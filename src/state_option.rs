@@ -0,0 +1,177 @@
+use bevy::prelude::*;
+
+use crate::{state_history, state_scoped};
+
+/// Queues a transition of an optional rollback state, which may be absent.
+///
+/// Optional states are added with
+/// [`RollApp::insert_roll_state_option`](crate::RollApp::insert_roll_state_option).
+/// As with Bevy's optional states, [`State<S>`] only exists while the state is
+/// `Some`, so `in_state` and `Option<Res<State<S>>>` work as usual. Use this
+/// resource instead of [`NextState<S>`] to queue transitions, including to and
+/// from `None`:
+///
+/// - `None` to `Some(entered)` runs `OnEnter(entered)`
+/// - `Some(exited)` to `None` runs `OnExit(exited)`
+/// - `Some(exited)` to `Some(entered)` runs `OnExit`, `OnTransition` and
+///   `OnEnter`, as for regular states
+///
+/// With the `bevy_ggrs` feature, the state is snapshotted as `Option<S>`.
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub enum RollStateOption<S: States> {
+    /// No transition is queued.
+    #[default]
+    Unchanged,
+    /// The state will be set to this value in the next transition.
+    Pending(Option<S>),
+}
+
+impl<S: States> RollStateOption<S> {
+    /// Queue a transition to the given state, `None` removes the state.
+    pub fn set(&mut self, state: Option<S>) {
+        *self = Self::Pending(state);
+    }
+
+    /// Remove any queued transition.
+    pub fn reset(&mut self) {
+        *self = Self::Unchanged;
+    }
+}
+
+/// If a new value is queued in [`RollStateOption<S>`], this system:
+/// - Inserts, updates or removes [`State<S>`]
+/// - Runs the [`OnExit`]`(exited_state)` schedule, if a state was exited and it exists.
+/// - Runs the [`OnTransition { from: exited_state, to: entered_state }`](OnTransition)
+///   schedule, if the state was changed and it exists.
+/// - Runs the [`OnEnter`]`(entered_state)` schedule, if a state was entered and it exists.
+pub fn apply_state_option_transition<S: States>(world: &mut World) {
+    let Some(mut next_state_resource) = world.get_resource_mut::<RollStateOption<S>>() else {
+        return;
+    };
+    let RollStateOption::Pending(entered) = next_state_resource.bypass_change_detection() else {
+        return;
+    };
+    let entered = entered.clone();
+    *next_state_resource = RollStateOption::Unchanged;
+
+    let exited = world
+        .get_resource::<State<S>>()
+        .map(|state| state.get().clone());
+    if exited == entered {
+        return;
+    }
+
+    match &entered {
+        Some(entered) => world.insert_resource(State::new(entered.clone())),
+        None => {
+            world.remove_resource::<State<S>>();
+        }
+    }
+    state_history::record_transition(world, exited.clone(), entered.clone());
+
+    if let Some(exited) = exited.clone() {
+        world.try_run_schedule(OnExit(exited.clone())).ok();
+        state_scoped::despawn_on_exit(world, exited);
+    }
+    if let (Some(exited), Some(entered)) = (exited, entered.clone()) {
+        world
+            .try_run_schedule(OnTransition { exited, entered })
+            .ok();
+    }
+    if let Some(entered) = entered {
+        world.try_run_schedule(OnEnter(entered.clone())).ok();
+        state_scoped::despawn_on_enter(world, entered);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RollApp, RollStateHistory, RollbackSchedulePlugin};
+
+    #[derive(States, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    enum Powerup {
+        Shield,
+        Speed,
+    }
+
+    #[derive(Resource, Default)]
+    struct Log(Vec<String>);
+
+    fn log(message: &'static str) -> impl Fn(ResMut<Log>) {
+        move |mut log: ResMut<Log>| log.0.push(message.to_string())
+    }
+
+    #[test]
+    fn transitions_to_and_from_none() {
+        let mut app = App::new();
+        app.add_plugins(RollbackSchedulePlugin::new(Update))
+            .init_resource::<Log>()
+            .insert_roll_state_option::<Powerup>(None)
+            .init_resource::<RollStateHistory<Powerup>>()
+            .add_systems(OnEnter(Powerup::Shield), log("enter shield"))
+            .add_systems(OnExit(Powerup::Shield), log("exit shield"))
+            .add_systems(OnEnter(Powerup::Speed), log("enter speed"))
+            .add_systems(OnExit(Powerup::Speed), log("exit speed"));
+
+        app.update();
+        assert!(!app.world().contains_resource::<State<Powerup>>());
+
+        let set = |app: &mut App, state| {
+            app.world_mut()
+                .resource_mut::<RollStateOption<Powerup>>()
+                .set(state);
+            app.update();
+        };
+
+        set(&mut app, Some(Powerup::Shield));
+        set(&mut app, Some(Powerup::Speed));
+        set(&mut app, None);
+        assert!(!app.world().contains_resource::<State<Powerup>>());
+
+        assert_eq!(
+            app.world().resource::<Log>().0,
+            vec!["enter shield", "exit shield", "enter speed", "exit speed"]
+        );
+
+        let history: Vec<_> = app
+            .world()
+            .resource::<RollStateHistory<Powerup>>()
+            .records()
+            .map(|record| (record.exited, record.entered))
+            .collect();
+        assert_eq!(
+            history,
+            vec![
+                (None, Some(Powerup::Shield)),
+                (Some(Powerup::Shield), Some(Powerup::Speed)),
+                (Some(Powerup::Speed), None),
+            ]
+        );
+    }
+
+    #[cfg(feature = "bevy_ggrs")]
+    #[test]
+    fn absence_is_rolled_back() {
+        use bevy_ggrs::{LoadWorld, RollbackFrameCount, SaveWorld, SnapshotPlugin};
+
+        let mut app = App::new();
+        app.add_plugins((SnapshotPlugin, RollbackSchedulePlugin::new(Update)))
+            .insert_ggrs_state_option::<Powerup>(None);
+
+        app.update();
+        app.world_mut().run_schedule(SaveWorld);
+
+        app.world_mut()
+            .resource_mut::<RollStateOption<Powerup>>()
+            .set(Some(Powerup::Shield));
+        app.update();
+        app.world_mut().resource_mut::<RollbackFrameCount>().0 = 1;
+        app.world_mut().run_schedule(SaveWorld);
+        assert!(app.world().contains_resource::<State<Powerup>>());
+
+        app.world_mut().resource_mut::<RollbackFrameCount>().0 = 0;
+        app.world_mut().run_schedule(LoadWorld);
+        assert!(!app.world().contains_resource::<State<Powerup>>());
+    }
+}
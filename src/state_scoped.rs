@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+
+use crate::RollQuery;
+
+/// Despawns the entity when the rollback state `S` exits the given state.
+///
+/// Rollback-safe replacement for Bevy's `DespawnOnExit`, which relies on
+/// transition events that rollback states don't send. Entities are despawned
+/// right after the `OnExit` schedule runs, in [`RollQuery`] order.
+///
+/// With the `bevy_ggrs` feature, the component is registered for rollback
+/// and checksumming by `init_ggrs_state` and the like.
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_roll_safe::RollDespawnOnExit;
+/// # #[derive(States, Hash, Default, Debug, Eq, PartialEq, Clone)]
+/// # enum GameplayState { #[default] InRound, GameOver }
+/// fn spawn_player(mut commands: Commands) {
+///     commands.spawn((Transform::default(), RollDespawnOnExit(GameplayState::InRound)));
+/// }
+/// ```
+#[derive(Component, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RollDespawnOnExit<S: States>(pub S);
+
+/// Despawns the entity when the rollback state `S` enters the given state.
+///
+/// Rollback-safe replacement for Bevy's `DespawnOnEnter`. Entities are
+/// despawned right after the `OnEnter` schedule runs, in [`RollQuery`] order.
+#[derive(Component, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RollDespawnOnEnter<S: States>(pub S);
+
+/// Despawns entities scoped to the exited state.
+pub(crate) fn despawn_on_exit<S: States>(world: &mut World, exited: S) {
+    if let Err(error) = world.run_system_cached_with(despawn_exited::<S>, exited) {
+        warn!("Failed to despawn state scoped entities: {error}");
+    }
+}
+
+/// Despawns entities scoped to the entered state.
+pub(crate) fn despawn_on_enter<S: States>(world: &mut World, entered: S) {
+    if let Err(error) = world.run_system_cached_with(despawn_entered::<S>, entered) {
+        warn!("Failed to despawn state scoped entities: {error}");
+    }
+}
+
+fn despawn_exited<S: States>(
+    In(exited): In<S>,
+    mut commands: Commands,
+    scoped: RollQuery<(Entity, &RollDespawnOnExit<S>)>,
+) {
+    for (entity, scope) in scoped.iter() {
+        if scope.0 == exited {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn despawn_entered<S: States>(
+    In(entered): In<S>,
+    mut commands: Commands,
+    scoped: RollQuery<(Entity, &RollDespawnOnEnter<S>)>,
+) {
+    for (entity, scope) in scoped.iter() {
+        if scope.0 == entered {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RollApp, RollbackSchedulePlugin};
+
+    #[derive(States, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    enum Round {
+        Playing,
+        Scoring,
+    }
+
+    #[test]
+    fn despawns_scoped_entities() {
+        let mut app = App::new();
+        app.add_plugins(RollbackSchedulePlugin::new(Update))
+            .insert_roll_state(Round::Playing);
+
+        let player = app
+            .world_mut()
+            .spawn(RollDespawnOnExit(Round::Playing))
+            .id();
+        let banner = app
+            .world_mut()
+            .spawn(RollDespawnOnEnter(Round::Scoring))
+            .id();
+        let scoreboard = app
+            .world_mut()
+            .spawn(RollDespawnOnExit(Round::Scoring))
+            .id();
+
+        app.update();
+        assert!(app.world().get_entity(player).is_ok());
+
+        app.world_mut()
+            .resource_mut::<NextState<Round>>()
+            .set(Round::Scoring);
+        app.update();
+
+        assert!(app.world().get_entity(player).is_err());
+        assert!(app.world().get_entity(banner).is_err());
+        assert!(app.world().get_entity(scoreboard).is_ok());
+    }
+
+    #[test]
+    fn session_reset_despawns_scoped_entities() {
+        use crate::RollbackSessionReset;
+
+        let mut app = App::new();
+        app.add_plugins(RollbackSchedulePlugin::new(Update))
+            .insert_roll_state(Round::Playing);
+        app.update();
+
+        app.world_mut()
+            .resource_mut::<NextState<Round>>()
+            .set(Round::Scoring);
+        app.update();
+        let scoreboard = app
+            .world_mut()
+            .spawn(RollDespawnOnExit(Round::Scoring))
+            .id();
+
+        app.world_mut().trigger(RollbackSessionReset);
+        app.world_mut().flush();
+
+        assert!(app.world().get_entity(scoreboard).is_err());
+        assert_eq!(
+            *app.world().resource::<State<Round>>().get(),
+            Round::Playing
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use bevy::{prelude::*, reflect::PartialReflect, state::state::FreelyMutableState};
+use bevy_ggrs::{checksum_hasher, ChecksumFlag, ChecksumPart, Rollback, Strategy};
+
+/// A [`Strategy`] for [`State<S>`], which doesn't implement `Clone`.
+///
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use bevy_ggrs::ResourceSnapshotPlugin;
+/// # use bevy_roll_safe::StateStrategy;
+/// # #[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+/// # enum GameState { #[default] Playing }
+/// # fn start() {
+/// # let mut app = App::new();
+/// app.add_plugins(ResourceSnapshotPlugin::<StateStrategy<GameState>>::default());
+/// # }
+/// ```
+pub struct StateStrategy<S: States>(PhantomData<S>);
+
+// todo: make State<S> implement clone instead
+impl<S: States> Strategy for StateStrategy<S> {
+    type Target = State<S>;
+    type Stored = S;
+
+    fn store(target: &Self::Target) -> Self::Stored {
+        target.get().to_owned()
+    }
+
+    fn load(stored: &Self::Stored) -> Self::Target {
+        State::new(stored.to_owned())
+    }
+}
+
+/// A [`Strategy`] for [`NextState<S>`], storing only the pending state.
+pub struct NextStateStrategy<S: States>(PhantomData<S>);
+
+impl<S: States + FreelyMutableState> Strategy for NextStateStrategy<S> {
+    type Target = NextState<S>;
+    type Stored = Option<S>;
+
+    fn store(target: &Self::Target) -> Self::Stored {
+        match target {
+            NextState::Unchanged => None,
+            NextState::Pending(s) => Some(s.to_owned()),
+        }
+    }
+
+    fn load(stored: &Self::Stored) -> Self::Target {
+        match stored {
+            None => NextState::Unchanged,
+            Some(s) => NextState::Pending(s.to_owned()),
+        }
+    }
+}
+
+/// A [`Strategy`] based on [`FromReflect`], for types that are neither
+/// `Clone` nor [`FromWorld`], such as [`State<S>`] and [`Messages<M>`].
+///
+/// Unlike `bevy_ggrs`' `ReflectStrategy`, loading doesn't need a default
+/// value to apply the snapshot to, so the snapshot must contain all fields.
+///
+/// See [`RollApp::rollback_resource_with_from_reflect`](crate::RollApp::rollback_resource_with_from_reflect).
+pub struct FromReflectStrategy<T: FromReflect>(PhantomData<T>);
+
+impl<T: FromReflect> Strategy for FromReflectStrategy<T> {
+    type Target = T;
+    type Stored = Box<dyn PartialReflect>;
+
+    fn store(target: &Self::Target) -> Self::Stored {
+        target.to_dynamic()
+    }
+
+    fn load(stored: &Self::Stored) -> Self::Target {
+        T::from_reflect(stored.as_ref()).unwrap_or_else(|| {
+            panic!(
+                "failed to load {} from its snapshot",
+                std::any::type_name::<T>()
+            )
+        })
+    }
+
+    fn update(target: &mut Self::Target, stored: &Self::Stored) {
+        target.apply(stored.as_ref());
+    }
+}
+
+/// Checksum for [`State<S>`], which doesn't implement [`Hash`].
+pub(crate) fn checksum_state<S: States>(state: &State<S>) -> u64 {
+    let mut hasher = checksum_hasher();
+    state.get().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Checksum for [`NextState<S>`], which doesn't implement [`Hash`].
+pub(crate) fn checksum_next_state<S: FreelyMutableState>(next_state: &NextState<S>) -> u64 {
+    let mut hasher = checksum_hasher();
+    NextStateStrategy::store(next_state).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Checksum for an optional [`State<S>`], which may be absent.
+#[allow(clippy::type_complexity)]
+pub(crate) fn checksum_state_option<S: States>(
+    mut commands: Commands,
+    state: Option<Res<State<S>>>,
+    mut checksum: Query<&mut ChecksumPart, (Without<Rollback>, With<ChecksumFlag<State<S>>>)>,
+) {
+    let mut hasher = checksum_hasher();
+    state.map(|state| state.get().clone()).hash(&mut hasher);
+    let result = ChecksumPart(hasher.finish() as u128);
+
+    if let Ok(mut checksum) = checksum.single_mut() {
+        *checksum = result;
+    } else {
+        commands.spawn((result, ChecksumFlag::<State<S>>::default()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(States, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    enum GameState {
+        #[default]
+        Menu,
+        Playing,
+    }
+
+    #[test]
+    fn state_strategies_round_trip() {
+        let state = StateStrategy::<GameState>::load(&StateStrategy::store(&State::new(
+            GameState::Playing,
+        )));
+        assert_eq!(*state.get(), GameState::Playing);
+
+        let next = NextStateStrategy::<GameState>::load(&NextStateStrategy::store(
+            &NextState::Pending(GameState::Menu),
+        ));
+        assert!(matches!(next, NextState::Pending(GameState::Menu)));
+    }
+
+    #[test]
+    fn from_reflect_strategy_round_trip() {
+        let stored =
+            FromReflectStrategy::<State<GameState>>::store(&State::new(GameState::Playing));
+
+        let loaded = FromReflectStrategy::<State<GameState>>::load(&stored);
+        assert_eq!(*loaded.get(), GameState::Playing);
+
+        let mut target = State::new(GameState::Menu);
+        FromReflectStrategy::update(&mut target, &stored);
+        assert_eq!(*target.get(), GameState::Playing);
+    }
+
+    #[test]
+    fn state_checksums_differ() {
+        assert_ne!(
+            checksum_state(&State::new(GameState::Menu)),
+            checksum_state(&State::new(GameState::Playing))
+        );
+        assert_ne!(
+            checksum_next_state::<GameState>(&NextState::Unchanged),
+            checksum_next_state(&NextState::Pending(GameState::Menu))
+        );
+    }
+
+    #[test]
+    fn ggrs_states_are_checksummed() {
+        use crate::{RollApp, RollbackSchedulePlugin};
+        use bevy_ggrs::{Checksum, ChecksumPlugin, GgrsSchedule, SaveWorld, SnapshotPlugin};
+
+        let mut app = App::new();
+        app.add_plugins((
+            SnapshotPlugin,
+            ChecksumPlugin,
+            RollbackSchedulePlugin::new_ggrs(),
+        ))
+        .init_ggrs_state::<GameState>();
+
+        let checksum = |app: &mut App| {
+            app.world_mut().run_schedule(SaveWorld);
+            app.world().resource::<Checksum>().0
+        };
+
+        app.world_mut().run_schedule(GgrsSchedule);
+        let menu = checksum(&mut app);
+
+        app.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Playing);
+        let pending = checksum(&mut app);
+        assert_ne!(pending, menu);
+
+        app.world_mut().run_schedule(GgrsSchedule);
+        assert_eq!(
+            *app.world().resource::<State<GameState>>().get(),
+            GameState::Playing
+        );
+        let playing = checksum(&mut app);
+        assert_ne!(playing, menu);
+        assert_ne!(playing, pending);
+    }
+}
@@ -0,0 +1,35 @@
+//! Fixtures shared by the unit tests.
+
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+
+/// Parent schedule for tests that run rollback frames by hand.
+///
+/// Deliberately not called `Rollback`, which would shadow `bevy_ggrs::Rollback`.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct TestRollbackSchedule;
+
+/// Creates an app with [`SnapshotPlugin`](bevy_ggrs::SnapshotPlugin) and the rollback schedules
+/// in [`GgrsSchedule`](bevy_ggrs::GgrsSchedule), which is run by
+/// [`AdvanceWorld`](bevy_ggrs::AdvanceWorld).
+#[cfg(feature = "bevy_ggrs")]
+pub(crate) fn ggrs_test_app() -> App {
+    use bevy_ggrs::{AdvanceWorld, GgrsSchedule, SnapshotPlugin};
+
+    let mut app = App::new();
+    app.add_plugins((SnapshotPlugin, crate::RollbackSchedulePlugin::new_ggrs()))
+        // `GgrsPlugin` would do this, but it needs a session
+        .add_systems(AdvanceWorld, |world: &mut World| {
+            world.try_run_schedule(GgrsSchedule).unwrap();
+        });
+    app
+}
+
+/// Advances to the next frame and saves it, like `bevy_ggrs` does during a session.
+#[cfg(feature = "bevy_ggrs")]
+pub(crate) fn advance_and_save(world: &mut World) {
+    use bevy_ggrs::{AdvanceWorld, RollbackFrameCount, SaveWorld};
+
+    world.resource_mut::<RollbackFrameCount>().0 += 1;
+    world.run_schedule(AdvanceWorld);
+    world.run_schedule(SaveWorld);
+}